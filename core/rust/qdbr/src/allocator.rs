@@ -0,0 +1,123 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Global allocator. With the `jemalloc` feature the library allocates through jemalloc,
+//! which copes better with many short-lived buffers than the system allocator, and reports
+//! allocation statistics.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use crate::diagnostics::catch_panic;
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(any(feature = "jemalloc", test))]
+pub const STAT_ALLOCATED: jint = 0;
+#[cfg(any(feature = "jemalloc", test))]
+pub const STAT_ACTIVE: jint = 1;
+#[cfg(any(feature = "jemalloc", test))]
+pub const STAT_RESIDENT: jint = 2;
+/// Resident bytes not backing live allocations: unused space in active pages, dirty pages
+/// not yet returned to the OS and allocator metadata.
+#[cfg(any(feature = "jemalloc", test))]
+pub const STAT_FRAGMENTATION: jint = 3;
+
+#[cfg(feature = "jemalloc")]
+fn stat(stat: jint) -> Option<usize> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+    // Statistics are cached by jemalloc until the epoch is advanced.
+    epoch::advance().ok()?;
+    match stat {
+        STAT_ALLOCATED => stats::allocated::read().ok(),
+        STAT_ACTIVE => stats::active::read().ok(),
+        STAT_RESIDENT => stats::resident::read().ok(),
+        STAT_FRAGMENTATION => {
+            let allocated = stats::allocated::read().ok()?;
+            Some(stats::resident::read().ok()?.saturating_sub(allocated))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn stat(_stat: jint) -> Option<usize> {
+    None
+}
+
+/// Returns the statistic in bytes, or -1 for an unknown statistic or when the library is
+/// built without allocator statistics.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Os_rustAllocatorStat(
+    _env: JNIEnv,
+    _class: JClass,
+    stat_id: jint,
+) -> jlong {
+    catch_panic(|| stat(stat_id).map_or(-1, |v| v as jlong))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(stat_id: jint) -> jlong {
+        Java_io_questdb_std_Os_rustAllocatorStat(crate::test_env(), JClass::default(), stat_id)
+    }
+
+    #[test]
+    fn rejects_unknown_stats() {
+        for stat_id in [-1, 4, jint::MAX] {
+            assert_eq!(read(stat_id), -1);
+        }
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn reports_jemalloc_stats() {
+        let buffer = vec![1u8; 1 << 20];
+        let allocated = read(STAT_ALLOCATED);
+        let active = read(STAT_ACTIVE);
+        let resident = read(STAT_RESIDENT);
+        assert!(allocated >= buffer.len() as jlong);
+        assert!(active >= allocated);
+        assert!(resident >= active);
+        assert!(read(STAT_FRAGMENTATION) >= 0);
+    }
+
+    #[cfg(not(feature = "jemalloc"))]
+    #[test]
+    fn no_stats_without_jemalloc() {
+        for stat_id in [
+            STAT_ALLOCATED,
+            STAT_ACTIVE,
+            STAT_RESIDENT,
+            STAT_FRAGMENTATION,
+        ] {
+            assert_eq!(read(stat_id), -1);
+        }
+    }
+}
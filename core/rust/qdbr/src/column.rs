@@ -0,0 +1,489 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Read access to column storage handed over from Java, one value per row.
+//!
+//! Java describes each column with a [`ColumnDesc`] pointing at its mapped files. Row access
+//! is bounds checked against the declared sizes, so a truncated or corrupt column surfaces as
+//! [`ColumnError::Corrupt`] instead of reading outside the mapping.
+
+use crate::column_type::{self as ct, INT_NULL, LONG_NULL, SYMBOL_NULL};
+use crate::mem;
+
+/// Native layout of a column description, filled in by Java at the offsets of
+/// `io.questdb.std.ColumnDesc`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ColumnDesc {
+    pub column_type: i32,
+    pub name_size: i32,
+    /// UTF-8 column name.
+    pub name: *const u8,
+    pub data: *const u8,
+    pub data_size: i64,
+    /// Offsets for STRING, 16-byte entries for VARCHAR, unused otherwise.
+    pub aux: *const u8,
+    pub aux_size: i64,
+    /// SYMBOL only: per-key offsets into `symbol_chars`, past the `.o` file header.
+    pub symbol_offsets: *const i64,
+    pub symbol_count: i64,
+    /// SYMBOL only: the `.c` file.
+    pub symbol_chars: *const u8,
+    pub symbol_chars_size: i64,
+}
+
+// The layout is mirrored by the offsets of io.questdb.std.ColumnDesc.
+const _: () = {
+    use std::mem::{offset_of, size_of};
+    assert!(offset_of!(ColumnDesc, column_type) == 0);
+    assert!(offset_of!(ColumnDesc, name_size) == 4);
+    assert!(offset_of!(ColumnDesc, name) == 8);
+    assert!(offset_of!(ColumnDesc, data) == 16);
+    assert!(offset_of!(ColumnDesc, data_size) == 24);
+    assert!(offset_of!(ColumnDesc, aux) == 32);
+    assert!(offset_of!(ColumnDesc, aux_size) == 40);
+    assert!(offset_of!(ColumnDesc, symbol_offsets) == 48);
+    assert!(offset_of!(ColumnDesc, symbol_count) == 56);
+    assert!(offset_of!(ColumnDesc, symbol_chars) == 64);
+    assert!(offset_of!(ColumnDesc, symbol_chars_size) == 72);
+    assert!(size_of::<ColumnDesc>() == 80);
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnError {
+    UnsupportedType,
+    Corrupt,
+}
+
+impl ColumnError {
+    pub fn code(self) -> i64 {
+        match self {
+            ColumnError::UnsupportedType => -1,
+            ColumnError::Corrupt => -2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Boolean(bool),
+    /// BYTE, SHORT, INT and LONG.
+    Long(i64),
+    /// Never 0, which is the null CHAR.
+    Char(u16),
+    Date(i64),
+    Timestamp(i64),
+    Float(f32),
+    Double(f64),
+    Ipv4(u32),
+    /// Hash and precision in bits.
+    GeoHash(i64, u32),
+    /// `(lo, hi)`
+    Uuid(u64, u64),
+    Long256([u64; 4]),
+    /// UTF-16LE chars of a STRING or SYMBOL value.
+    Utf16(&'a [u8]),
+    /// VARCHAR bytes.
+    Utf8(&'a [u8]),
+}
+
+pub struct Column<'a> {
+    pub name: &'a [u8],
+    column_type: i32,
+    data: &'a [u8],
+    aux: &'a [u8],
+    symbol_offsets: &'a [i64],
+    symbol_chars: &'a [u8],
+    row_count: usize,
+}
+
+const VARCHAR_AUX_SIZE: usize = 16;
+const VARCHAR_INLINED: u32 = 1;
+const VARCHAR_NULL: u32 = 4;
+const VARCHAR_MAX_INLINED_SIZE: usize = 9;
+const VARCHAR_DATA_OFFSET: usize = 10;
+
+fn fixed_size(tag: i32) -> Option<usize> {
+    Some(match tag {
+        ct::BOOLEAN | ct::BYTE | ct::GEOBYTE => 1,
+        ct::SHORT | ct::CHAR | ct::GEOSHORT => 2,
+        ct::INT | ct::FLOAT | ct::SYMBOL | ct::GEOINT | ct::IPV4 => 4,
+        ct::LONG | ct::DATE | ct::TIMESTAMP | ct::DOUBLE | ct::GEOLONG => 8,
+        ct::UUID => 16,
+        ct::LONG256 => 32,
+        _ => return None,
+    })
+}
+
+fn read<const N: usize>(buf: &[u8], offset: usize) -> Option<[u8; N]> {
+    buf.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+fn read_i64(buf: &[u8], offset: usize) -> Option<i64> {
+    read(buf, offset).map(i64::from_le_bytes)
+}
+
+fn read_i32(buf: &[u8], offset: usize) -> Option<i32> {
+    read(buf, offset).map(i32::from_le_bytes)
+}
+
+impl<'a> Column<'a> {
+    /// # Safety
+    /// The pointers in `desc` must be valid for reads of their declared sizes for `'a`.
+    pub unsafe fn new(desc: &ColumnDesc) -> Result<Self, ColumnError> {
+        let tag = ct::tag(desc.column_type);
+        let sizes = [desc.name_size as i64, desc.data_size, desc.aux_size];
+        if sizes.iter().any(|&s| s < 0) || desc.symbol_count < 0 || desc.symbol_chars_size < 0 {
+            return Err(ColumnError::Corrupt);
+        }
+        let data = mem::slice(desc.data, desc.data_size);
+        let aux = mem::slice(desc.aux, desc.aux_size);
+        let row_count = match tag {
+            ct::STRING => aux.len() / 8,
+            ct::VARCHAR => aux.len() / VARCHAR_AUX_SIZE,
+            _ => data.len() / fixed_size(tag).ok_or(ColumnError::UnsupportedType)?,
+        };
+        let (symbol_offsets, symbol_chars) = if tag == ct::SYMBOL {
+            (
+                mem::slice(desc.symbol_offsets, desc.symbol_count),
+                mem::slice(desc.symbol_chars, desc.symbol_chars_size),
+            )
+        } else {
+            (&[][..], &[][..])
+        };
+        Ok(Column {
+            name: mem::slice(desc.name, desc.name_size as i64),
+            column_type: desc.column_type,
+            data,
+            aux,
+            symbol_offsets,
+            symbol_chars,
+            row_count,
+        })
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn tag(&self) -> i32 {
+        ct::tag(self.column_type)
+    }
+
+    pub fn is_fixed_size(&self) -> bool {
+        fixed_size(self.tag()).is_some()
+    }
+
+    /// Stored bytes of a fixed-size value.
+    pub fn stored(&self, row: usize) -> Result<&'a [u8], ColumnError> {
+        let size = fixed_size(self.tag()).ok_or(ColumnError::UnsupportedType)?;
+        let at = row.checked_mul(size).ok_or(ColumnError::Corrupt)?;
+        self.data.get(at..at + size).ok_or(ColumnError::Corrupt)
+    }
+
+    pub fn value(&self, row: usize) -> Result<Value<'a>, ColumnError> {
+        self.read_value(row).ok_or(ColumnError::Corrupt)
+    }
+
+    fn read_value(&self, row: usize) -> Option<Value<'a>> {
+        let tag = self.tag();
+        let data = self.data;
+        let at = row.checked_mul(fixed_size(tag).unwrap_or(0))?;
+        Some(match tag {
+            ct::BOOLEAN => Value::Boolean(*data.get(at)? != 0),
+            ct::BYTE => Value::Long(*data.get(at)? as i8 as i64),
+            ct::SHORT => Value::Long(i16::from_le_bytes(read(data, at)?) as i64),
+            ct::CHAR => match u16::from_le_bytes(read(data, at)?) {
+                0 => Value::Null,
+                c => Value::Char(c),
+            },
+            ct::INT => match read_i32(data, at)? {
+                INT_NULL => Value::Null,
+                v => Value::Long(v as i64),
+            },
+            ct::LONG | ct::DATE | ct::TIMESTAMP => match read_i64(data, at)? {
+                LONG_NULL => Value::Null,
+                v if tag == ct::DATE => Value::Date(v),
+                v if tag == ct::TIMESTAMP => Value::Timestamp(v),
+                v => Value::Long(v),
+            },
+            ct::FLOAT => match f32::from_le_bytes(read(data, at)?) {
+                v if v.is_nan() => Value::Null,
+                v => Value::Float(v),
+            },
+            ct::DOUBLE => match f64::from_le_bytes(read(data, at)?) {
+                v if v.is_nan() => Value::Null,
+                v => Value::Double(v),
+            },
+            ct::IPV4 => match u32::from_le_bytes(read(data, at)?) {
+                ct::IPV4_NULL => Value::Null,
+                v => Value::Ipv4(v),
+            },
+            ct::GEOBYTE | ct::GEOSHORT | ct::GEOINT | ct::GEOLONG => {
+                let hash = match tag {
+                    ct::GEOBYTE => *data.get(at)? as i8 as i64,
+                    ct::GEOSHORT => i16::from_le_bytes(read(data, at)?) as i64,
+                    ct::GEOINT => read_i32(data, at)? as i64,
+                    _ => read_i64(data, at)?,
+                };
+                match hash {
+                    ct::GEOHASH_NULL => Value::Null,
+                    v => Value::GeoHash(v, ct::geohash_bits(self.column_type)),
+                }
+            }
+            ct::UUID => {
+                let lo = read_i64(data, at)?;
+                let hi = read_i64(data, at + 8)?;
+                if lo == LONG_NULL && hi == LONG_NULL {
+                    Value::Null
+                } else {
+                    Value::Uuid(lo as u64, hi as u64)
+                }
+            }
+            ct::LONG256 => {
+                let mut words = [0u64; 4];
+                for (i, w) in words.iter_mut().enumerate() {
+                    *w = read_i64(data, at + i * 8)? as u64;
+                }
+                if words.iter().all(|&w| w == LONG_NULL as u64) {
+                    Value::Null
+                } else {
+                    Value::Long256(words)
+                }
+            }
+            ct::SYMBOL => match read_i32(data, at)? {
+                SYMBOL_NULL => Value::Null,
+                key => {
+                    let offset = *self.symbol_offsets.get(usize::try_from(key).ok()?)?;
+                    self.utf16_at(self.symbol_chars, offset)?
+                }
+            },
+            ct::STRING => {
+                let offset = read_i64(self.aux, row.checked_mul(8)?)?;
+                self.utf16_at(data, offset)?
+            }
+            ct::VARCHAR => self.varchar(row)?,
+            _ => return None,
+        })
+    }
+
+    /// Appends an encoding of the stored value that is equal for two rows exactly when their
+    /// stored values are equal. Fixed-size values, including symbol keys, are taken as is.
+    pub fn push_key(&self, row: usize, out: &mut Vec<u8>) -> Result<(), ColumnError> {
+        if self.is_fixed_size() {
+            out.extend_from_slice(self.stored(row)?);
+            return Ok(());
+        }
+        match self.value(row)? {
+            Value::Utf16(v) | Value::Utf8(v) => {
+                out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                out.extend_from_slice(v);
+            }
+            _ => out.extend_from_slice(&u32::MAX.to_le_bytes()),
+        }
+        Ok(())
+    }
+
+    /// A length-prefixed UTF-16 value, as stored in STRING data and symbol `.c` files.
+    fn utf16_at(&self, buf: &'a [u8], offset: i64) -> Option<Value<'a>> {
+        let offset = usize::try_from(offset).ok()?;
+        let len = read_i32(buf, offset)?;
+        if len < 0 {
+            return Some(Value::Null);
+        }
+        let lo = offset + 4;
+        Some(Value::Utf16(buf.get(lo..lo + len as usize * 2)?))
+    }
+
+    fn varchar(&self, row: usize) -> Option<Value<'a>> {
+        let at = row.checked_mul(VARCHAR_AUX_SIZE)?;
+        let entry = self.aux.get(at..at + VARCHAR_AUX_SIZE)?;
+        let header = u32::from_le_bytes(read(entry, 0)?);
+        if header & VARCHAR_NULL != 0 {
+            return Some(Value::Null);
+        }
+        if header & VARCHAR_INLINED != 0 {
+            let size = ((header >> 4) & 0xF) as usize;
+            if size > VARCHAR_MAX_INLINED_SIZE {
+                return None;
+            }
+            return Some(Value::Utf8(&entry[1..1 + size]));
+        }
+        let size = (header >> 4) as usize & ((1 << 28) - 1);
+        let offset_lo = u16::from_le_bytes(read(entry, VARCHAR_DATA_OFFSET)?) as u64;
+        let offset_hi = u32::from_le_bytes(read(entry, VARCHAR_DATA_OFFSET + 2)?) as u64;
+        let offset = usize::try_from(offset_lo | (offset_hi << 16)).ok()?;
+        Some(Value::Utf8(
+            self.data.get(offset..offset.checked_add(size)?)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+impl ColumnDesc {
+    /// Describes in-memory buffers, see [`string_column`] and [`varchar_column`] for the
+    /// var-size layouts.
+    pub fn of(column_type: i32, data: &[u8], aux: &[u8]) -> ColumnDesc {
+        ColumnDesc {
+            column_type,
+            name_size: 0,
+            name: std::ptr::null(),
+            data: data.as_ptr(),
+            data_size: data.len() as i64,
+            aux: aux.as_ptr(),
+            aux_size: aux.len() as i64,
+            symbol_offsets: std::ptr::null(),
+            symbol_count: 0,
+            symbol_chars: std::ptr::null(),
+            symbol_chars_size: 0,
+        }
+    }
+}
+
+/// `(data, aux)` of a STRING column.
+#[cfg(test)]
+pub fn string_column(values: &[Option<&str>]) -> (Vec<u8>, Vec<u8>) {
+    let (mut data, mut aux) = (Vec::new(), Vec::new());
+    for value in values {
+        aux.extend_from_slice(&(data.len() as i64).to_le_bytes());
+        match value {
+            Some(v) => {
+                let chars: Vec<u16> = v.encode_utf16().collect();
+                data.extend_from_slice(&(chars.len() as i32).to_le_bytes());
+                chars
+                    .iter()
+                    .for_each(|c| data.extend_from_slice(&c.to_le_bytes()));
+            }
+            None => data.extend_from_slice(&(-1i32).to_le_bytes()),
+        }
+    }
+    (data, aux)
+}
+
+/// `(data, aux)` of a VARCHAR column.
+#[cfg(test)]
+pub fn varchar_column(values: &[Option<&str>]) -> (Vec<u8>, Vec<u8>) {
+    let (mut data, mut aux) = (Vec::new(), Vec::new());
+    for value in values {
+        let mut entry = [0u8; VARCHAR_AUX_SIZE];
+        match value {
+            None => entry[..4].copy_from_slice(&VARCHAR_NULL.to_le_bytes()),
+            Some(v) if v.len() <= VARCHAR_MAX_INLINED_SIZE => {
+                entry[0] = VARCHAR_INLINED as u8 | (v.len() as u8) << 4;
+                entry[1..1 + v.len()].copy_from_slice(v.as_bytes());
+            }
+            Some(v) => {
+                entry[..4].copy_from_slice(&((v.len() as u32) << 4).to_le_bytes());
+                let offset = (data.len() as u64).to_le_bytes();
+                entry[VARCHAR_DATA_OFFSET..].copy_from_slice(&offset[..6]);
+                data.extend_from_slice(v.as_bytes());
+            }
+        }
+        aux.extend_from_slice(&entry);
+    }
+    (data, aux)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values<'a>(column_type: i32, data: &'a [u8], aux: &'a [u8]) -> Vec<Value<'a>> {
+        let column = unsafe { Column::new(&ColumnDesc::of(column_type, data, aux)) }.unwrap();
+        (0..column.row_count())
+            .map(|row| column.value(row).unwrap())
+            .collect()
+    }
+
+    fn le<const N: usize, T>(values: &[T], to_le: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(to_le).collect()
+    }
+
+    #[test]
+    fn null_sentinels() {
+        use Value::Null;
+        assert_eq!(
+            values(ct::CHAR, &le(&[0u16, 65], |v| v.to_le_bytes()), &[]),
+            [Null, Value::Char(65)]
+        );
+        assert_eq!(
+            values(ct::INT, &le(&[INT_NULL, 0], |v| v.to_le_bytes()), &[]),
+            [Null, Value::Long(0)]
+        );
+        assert_eq!(
+            values(
+                ct::TIMESTAMP,
+                &le(&[LONG_NULL, 0], |v| v.to_le_bytes()),
+                &[]
+            ),
+            [Null, Value::Timestamp(0)]
+        );
+        assert_eq!(
+            values(ct::DOUBLE, &le(&[f64::NAN, 0.0], |v| v.to_le_bytes()), &[]),
+            [Null, Value::Double(0.0)]
+        );
+        assert_eq!(
+            values(ct::IPV4, &le(&[0u32, 1], |v| v.to_le_bytes()), &[]),
+            [Null, Value::Ipv4(1)]
+        );
+        assert_eq!(
+            values(ct::GEOBYTE, &[0xff, 3], &[]),
+            [Null, Value::GeoHash(3, 0)]
+        );
+        assert_eq!(
+            values(
+                ct::UUID,
+                &le(&[LONG_NULL, LONG_NULL, LONG_NULL, 1], |v| v.to_le_bytes()),
+                &[]
+            ),
+            [Null, Value::Uuid(LONG_NULL as u64, 1)]
+        );
+        // BOOLEAN, BYTE and SHORT have no null.
+        assert_eq!(values(ct::BYTE, &[0], &[]), [Value::Long(0)]);
+        let (data, aux) = string_column(&[None, Some("")]);
+        assert_eq!(values(ct::STRING, &data, &aux), [Null, Value::Utf16(&[])]);
+        let (data, aux) = varchar_column(&[None, Some(""), Some("long enough")]);
+        assert_eq!(
+            values(ct::VARCHAR, &data, &aux),
+            [Null, Value::Utf8(b""), Value::Utf8(b"long enough")]
+        );
+    }
+
+    #[test]
+    fn truncated_column() {
+        let (data, mut aux) = varchar_column(&[Some("long enough")]);
+        let column =
+            unsafe { Column::new(&ColumnDesc::of(ct::VARCHAR, &data[..4], &aux)) }.unwrap();
+        assert_eq!(column.value(0), Err(ColumnError::Corrupt));
+        aux.truncate(8);
+        let column = unsafe { Column::new(&ColumnDesc::of(ct::VARCHAR, &data, &aux)) }.unwrap();
+        assert_eq!(column.row_count(), 0);
+        let desc = ColumnDesc::of(ct::UNDEFINED, &[], &[]);
+        assert_eq!(
+            unsafe { Column::new(&desc) }.err(),
+            Some(ColumnError::UnsupportedType)
+        );
+    }
+}
@@ -0,0 +1,64 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Column type tags and null sentinels, mirroring `io.questdb.cairo.ColumnType` and
+//! `io.questdb.std.Numbers`.
+
+pub const UNDEFINED: i32 = 0;
+pub const BOOLEAN: i32 = 1;
+pub const BYTE: i32 = 2;
+pub const SHORT: i32 = 3;
+pub const CHAR: i32 = 4;
+pub const INT: i32 = 5;
+pub const LONG: i32 = 6;
+pub const DATE: i32 = 7;
+pub const TIMESTAMP: i32 = 8;
+pub const FLOAT: i32 = 9;
+pub const DOUBLE: i32 = 10;
+pub const STRING: i32 = 11;
+pub const SYMBOL: i32 = 12;
+pub const LONG256: i32 = 13;
+pub const GEOBYTE: i32 = 14;
+pub const GEOSHORT: i32 = 15;
+pub const GEOINT: i32 = 16;
+pub const GEOLONG: i32 = 17;
+pub const UUID: i32 = 19;
+pub const IPV4: i32 = 25;
+pub const VARCHAR: i32 = 26;
+
+pub const INT_NULL: i32 = i32::MIN;
+pub const LONG_NULL: i64 = i64::MIN;
+pub const SYMBOL_NULL: i32 = INT_NULL;
+pub const GEOHASH_NULL: i64 = -1;
+pub const IPV4_NULL: u32 = 0;
+
+/// Strips flags (e.g. the designated timestamp bit) and geohash precision off a type.
+pub fn tag(column_type: i32) -> i32 {
+    column_type & 0xFF
+}
+
+/// Precision in bits of a geohash type.
+pub fn geohash_bits(column_type: i32) -> u32 {
+    ((column_type >> 8) & 0xFF) as u32
+}
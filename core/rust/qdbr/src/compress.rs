@@ -0,0 +1,770 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Block compression for WAL segments and cold column files.
+//!
+//! Input is split into blocks of a fixed size, each compressed into an independent zstd frame
+//! with a content checksum. The frames are followed by a seek table in the zstd seekable
+//! format, so a range can be read back by decoding only the frames it overlaps, and the output
+//! stays readable by the `zstd` command line tool.
+//!
+//! Buffers that do not need seeking are compressed in one shot with any of the Parquet codecs
+//! linked into the library, selected by their Parquet `CompressionCodec` id.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+use std::io::{Read, Write};
+use zstd::zstd_safe::{compress_bound, CParameter};
+
+use crate::diagnostics::catch_panic;
+use crate::mem;
+
+const SKIPPABLE_FRAME_MAGIC: u32 = 0x184D_2A5E;
+const SEEKABLE_MAGIC: u32 = 0x8F92_EAB1;
+const SKIPPABLE_HEADER_SIZE: usize = 8;
+const SEEK_TABLE_FOOTER_SIZE: usize = 9;
+const SEEK_TABLE_CHECKSUM_FLAG: u8 = 0x80;
+const SEEK_TABLE_RESERVED_BITS: u8 = 0x7C;
+/// Upper bound of the block size, which also bounds the frame sizes a reader accepts from a
+/// seek table before it allocates for them.
+pub const MAX_BLOCK_SIZE: usize = 64 << 20;
+
+pub const CODEC_SNAPPY: jint = 1;
+pub const CODEC_GZIP: jint = 2;
+pub const CODEC_ZSTD: jint = 6;
+/// LZ4 block format without framing.
+pub const CODEC_LZ4_RAW: jint = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Codec {
+    Snappy,
+    Gzip,
+    Zstd,
+    Lz4Raw,
+}
+
+impl Codec {
+    fn from_id(id: jint) -> Option<Codec> {
+        match id {
+            CODEC_SNAPPY => Some(Codec::Snappy),
+            CODEC_GZIP => Some(Codec::Gzip),
+            CODEC_ZSTD => Some(Codec::Zstd),
+            CODEC_LZ4_RAW => Some(Codec::Lz4Raw),
+            _ => None,
+        }
+    }
+
+    /// Worst case compressed size, or None when the input is too large for the codec.
+    fn bound(self, src_size: usize) -> Option<usize> {
+        match self {
+            Codec::Snappy => Some(snap::raw::max_compress_len(src_size)).filter(|&n| n > 0),
+            // deflateBound() for stored blocks plus the 18 byte gzip header and trailer.
+            Codec::Gzip => Some(src_size + ((src_size + 7) >> 3) + ((src_size + 63) >> 6) + 23),
+            Codec::Zstd => Some(compress_bound(src_size)),
+            Codec::Lz4Raw => Some(lz4_flex::block::get_maximum_output_size(src_size)),
+        }
+    }
+}
+
+pub struct Compressor {
+    zstd: zstd::bulk::Compressor<'static>,
+    block_size: usize,
+    block: Vec<u8>,
+    /// Compressed and decompressed size of each frame written so far.
+    frames: Vec<(u32, u32)>,
+    out: Vec<u8>,
+    /// Set by `finish()`, the next stream has to be started with `reset()`.
+    finished: bool,
+}
+
+fn finished_error() -> std::io::Error {
+    std::io::Error::other("compressor is finished")
+}
+
+impl Compressor {
+    fn new(level: i32, block_size: usize) -> std::io::Result<Self> {
+        check_zstd_level(level)?;
+        let mut zstd = zstd::bulk::Compressor::new(level)?;
+        zstd.set_parameter(CParameter::ChecksumFlag(true))?;
+        Ok(Compressor {
+            zstd,
+            block_size,
+            block: Vec::with_capacity(block_size),
+            frames: Vec::new(),
+            out: Vec::new(),
+            finished: false,
+        })
+    }
+
+    fn compress(&mut self, mut src: &[u8]) -> std::io::Result<()> {
+        if self.finished {
+            return Err(finished_error());
+        }
+        while !src.is_empty() {
+            let n = (self.block_size - self.block.len()).min(src.len());
+            self.block.extend_from_slice(&src[..n]);
+            src = &src[n..];
+            if self.block.len() == self.block_size {
+                self.flush_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> std::io::Result<()> {
+        let size = self.out.len();
+        self.out.resize(size + compress_bound(self.block.len()), 0);
+        let compressed = self
+            .zstd
+            .compress_to_buffer(&self.block, &mut self.out[size..])?;
+        self.out.truncate(size + compressed);
+        self.frames
+            .push((compressed as u32, self.block.len() as u32));
+        self.block.clear();
+        Ok(())
+    }
+
+    /// Flushes the last block and appends the seek table, ending the stream.
+    fn finish(&mut self) -> std::io::Result<()> {
+        if self.finished {
+            return Err(finished_error());
+        }
+        self.finished = true;
+        if !self.block.is_empty() {
+            self.flush_block()?;
+        }
+        let entries_size = self.frames.len() * 8;
+        let out = &mut self.out;
+        out.extend_from_slice(&SKIPPABLE_FRAME_MAGIC.to_le_bytes());
+        out.extend_from_slice(&((entries_size + SEEK_TABLE_FOOTER_SIZE) as u32).to_le_bytes());
+        for (compressed, decompressed) in self.frames.drain(..) {
+            out.extend_from_slice(&compressed.to_le_bytes());
+            out.extend_from_slice(&decompressed.to_le_bytes());
+        }
+        out.extend_from_slice(&((entries_size / 8) as u32).to_le_bytes());
+        out.push(0);
+        out.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        Ok(())
+    }
+
+    /// Drops the output and any unfinished stream, and starts the next stream.
+    fn reset(&mut self) {
+        self.block.clear();
+        self.frames.clear();
+        self.out.clear();
+        self.finished = false;
+    }
+}
+
+struct Frame {
+    compressed_offset: usize,
+    compressed_size: usize,
+    decompressed_offset: u64,
+    decompressed_size: usize,
+}
+
+pub struct Reader<'a> {
+    src: &'a [u8],
+    frames: Vec<Frame>,
+    zstd: zstd::bulk::Decompressor<'static>,
+    /// Index of the frame held in `block`.
+    cached: Option<usize>,
+    block: Vec<u8>,
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+impl<'a> Reader<'a> {
+    fn new(src: &'a [u8]) -> Option<Self> {
+        let footer = src.len().checked_sub(SEEK_TABLE_FOOTER_SIZE)?;
+        let frame_count = read_u32(src, footer) as usize;
+        let descriptor = src[footer + 4];
+        if read_u32(src, footer + 5) != SEEKABLE_MAGIC || descriptor & SEEK_TABLE_RESERVED_BITS != 0
+        {
+            return None;
+        }
+        let entry_size = if descriptor & SEEK_TABLE_CHECKSUM_FLAG != 0 {
+            12
+        } else {
+            8
+        };
+        let table_size = frame_count.checked_mul(entry_size)? + SEEK_TABLE_FOOTER_SIZE;
+        let table = footer.checked_sub(table_size - SEEK_TABLE_FOOTER_SIZE)?;
+        let header = table.checked_sub(SKIPPABLE_HEADER_SIZE)?;
+        if read_u32(src, header) != SKIPPABLE_FRAME_MAGIC
+            || read_u32(src, header + 4) as usize != table_size
+        {
+            return None;
+        }
+        let mut frames = Vec::with_capacity(frame_count);
+        let (mut compressed_offset, mut decompressed_offset) = (0usize, 0u64);
+        for i in 0..frame_count {
+            let entry = table + i * entry_size;
+            let compressed_size = read_u32(src, entry) as usize;
+            let decompressed_size = read_u32(src, entry + 4) as usize;
+            if decompressed_size > MAX_BLOCK_SIZE {
+                return None;
+            }
+            frames.push(Frame {
+                compressed_offset,
+                compressed_size,
+                decompressed_offset,
+                decompressed_size,
+            });
+            compressed_offset += compressed_size;
+            decompressed_offset += decompressed_size as u64;
+        }
+        if compressed_offset != header {
+            return None;
+        }
+        Some(Reader {
+            src,
+            frames,
+            zstd: zstd::bulk::Decompressor::new().ok()?,
+            cached: None,
+            block: Vec::new(),
+        })
+    }
+
+    fn size(&self) -> u64 {
+        self.frames
+            .last()
+            .map_or(0, |f| f.decompressed_offset + f.decompressed_size as u64)
+    }
+
+    fn load(&mut self, index: usize) -> Option<()> {
+        if self.cached == Some(index) {
+            return Some(());
+        }
+        self.cached = None;
+        let frame = &self.frames[index];
+        let src =
+            &self.src[frame.compressed_offset..frame.compressed_offset + frame.compressed_size];
+        self.block.clear();
+        self.block.reserve(frame.decompressed_size);
+        match self.zstd.decompress_to_buffer(src, &mut self.block) {
+            Ok(size) if size == frame.decompressed_size => {}
+            Ok(size) => {
+                log::warn!(
+                    "unexpected decompressed frame size [frame={index}, expected={}, actual={size}]",
+                    frame.decompressed_size
+                );
+                return None;
+            }
+            Err(e) => {
+                log::warn!("could not decompress frame [frame={index}, error={e}]");
+                return None;
+            }
+        }
+        self.cached = Some(index);
+        Some(())
+    }
+
+    /// Reads up to `dst.len()` bytes at `offset`, returning the number of bytes read.
+    fn read(&mut self, offset: u64, dst: &mut [u8]) -> Option<usize> {
+        let mut index = self
+            .frames
+            .partition_point(|f| f.decompressed_offset + f.decompressed_size as u64 <= offset);
+        let mut written = 0;
+        while written < dst.len() && index < self.frames.len() {
+            self.load(index)?;
+            let lo = (offset + written as u64 - self.frames[index].decompressed_offset) as usize;
+            let n = (self.block.len() - lo).min(dst.len() - written);
+            dst[written..written + n].copy_from_slice(&self.block[lo..lo + n]);
+            written += n;
+            index += 1;
+        }
+        Some(written)
+    }
+}
+
+/// zstd clamps levels out of range, they are rejected instead.
+fn check_zstd_level(level: i32) -> std::io::Result<()> {
+    if zstd::compression_level_range().contains(&level) {
+        Ok(())
+    } else {
+        Err(std::io::Error::other("zstd level is out of range"))
+    }
+}
+
+fn compress_zstd(level: i32, src: &[u8], dst: &mut [u8]) -> std::io::Result<usize> {
+    check_zstd_level(level)?;
+    let mut zstd = zstd::bulk::Compressor::new(level)?;
+    zstd.set_parameter(CParameter::ChecksumFlag(true))?;
+    zstd.compress_to_buffer(src, dst)
+}
+
+/// Compresses `src` into `dst`, failing when `dst` is too small. Zstd output is a single frame
+/// with a content checksum. The level is a zstd level or 0..=9 for gzip, and is ignored by
+/// Snappy and LZ4.
+fn compress_buffer(codec: Codec, level: i32, src: &[u8], dst: &mut [u8]) -> std::io::Result<usize> {
+    match codec {
+        Codec::Snappy => Ok(snap::raw::Encoder::new().compress(src, dst)?),
+        Codec::Gzip => {
+            let level = u32::try_from(level)
+                .ok()
+                .filter(|&level| level <= 9)
+                .ok_or_else(|| std::io::Error::other("gzip level is not in 0..=9"))?;
+            let capacity = dst.len();
+            let mut out = &mut dst[..];
+            let mut gzip = flate2::write::GzEncoder::new(&mut out, flate2::Compression::new(level));
+            gzip.write_all(src)?;
+            gzip.finish()?;
+            Ok(capacity - out.len())
+        }
+        Codec::Zstd => compress_zstd(level, src, dst),
+        Codec::Lz4Raw => lz4_flex::block::compress_into(src, dst).map_err(std::io::Error::other),
+    }
+}
+
+/// Decompresses `src` into `dst`, failing when the input is corrupt or `dst` is too small.
+fn decompress_buffer(codec: Codec, src: &[u8], dst: &mut [u8]) -> std::io::Result<usize> {
+    match codec {
+        Codec::Snappy => Ok(snap::raw::Decoder::new().decompress(src, dst)?),
+        Codec::Gzip => {
+            let mut gzip = flate2::read::GzDecoder::new(src);
+            let mut size = 0;
+            while size < dst.len() {
+                match gzip.read(&mut dst[size..])? {
+                    0 => return Ok(size),
+                    n => size += n,
+                }
+            }
+            match gzip.read(&mut [0])? {
+                0 => Ok(size),
+                _ => Err(std::io::Error::other("destination is too small")),
+            }
+        }
+        Codec::Zstd => zstd::bulk::decompress_to_buffer(src, dst),
+        Codec::Lz4Raw => lz4_flex::block::decompress_into(src, dst).map_err(std::io::Error::other),
+    }
+}
+
+/// Returns the compressed size, or -1 for an unknown codec or level, or when `dst` is too
+/// small.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_compressBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+    codec: jint,
+    level: jint,
+    src: *const u8,
+    src_size: jlong,
+    dst: *mut u8,
+    dst_capacity: jlong,
+) -> jlong {
+    catch_panic(|| {
+        let Some(codec) = Codec::from_id(codec) else {
+            return -1;
+        };
+        let src = unsafe { mem::slice(src, src_size) };
+        let dst = unsafe { mem::slice_mut(dst, dst_capacity) };
+        compress_buffer(codec, level, src, dst).map_or(-1, |size| size as jlong)
+    })
+}
+
+/// Returns the worst case size of `compressBuffer()` output, or -1 for an unknown codec or an
+/// input too large for the codec.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_compressBufferBound(
+    _env: JNIEnv,
+    _class: JClass,
+    codec: jint,
+    src_size: jlong,
+) -> jlong {
+    catch_panic(|| match Codec::from_id(codec) {
+        Some(codec) if src_size >= 0 => codec
+            .bound(src_size as usize)
+            .map_or(-1, |bound| bound as jlong),
+        _ => -1,
+    })
+}
+
+/// Returns the decompressed size, or -1 for an unknown codec, when the input is corrupt or
+/// `dst` is too small.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_decompressBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+    codec: jint,
+    src: *const u8,
+    src_size: jlong,
+    dst: *mut u8,
+    dst_capacity: jlong,
+) -> jlong {
+    catch_panic(|| {
+        let Some(codec) = Codec::from_id(codec) else {
+            return -1;
+        };
+        let src = unsafe { mem::slice(src, src_size) };
+        let dst = unsafe { mem::slice_mut(dst, dst_capacity) };
+        decompress_buffer(codec, src, dst).map_or(-1, |size| size as jlong)
+    })
+}
+
+/// Returns null when the level is not supported or the block size is out of range.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_createCompressor(
+    _env: JNIEnv,
+    _class: JClass,
+    level: jint,
+    block_size: jint,
+) -> *mut Compressor {
+    catch_panic(|| {
+        if block_size <= 0 || block_size as usize > MAX_BLOCK_SIZE {
+            return std::ptr::null_mut();
+        }
+        match Compressor::new(level, block_size as usize) {
+            Ok(compressor) => Box::into_raw(Box::new(compressor)),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_destroyCompressor(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *mut Compressor,
+) {
+    catch_panic(|| {
+        if !compressor.is_null() {
+            drop(unsafe { Box::from_raw(compressor) });
+        }
+    })
+}
+
+/// Returns the size of the compressed output, or -1 on failure.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_compress(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *mut Compressor,
+    lo: *const u8,
+    size: jlong,
+) -> jlong {
+    catch_panic(|| {
+        let compressor = unsafe { &mut *compressor };
+        match compressor.compress(unsafe { mem::slice(lo, size) }) {
+            Ok(()) => compressor.out.len() as jlong,
+            Err(_) => -1,
+        }
+    })
+}
+
+/// Returns the size of the compressed output, or -1 on failure.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_finish(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *mut Compressor,
+) -> jlong {
+    catch_panic(|| {
+        let compressor = unsafe { &mut *compressor };
+        match compressor.finish() {
+            Ok(()) => compressor.out.len() as jlong,
+            Err(_) => -1,
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_reset(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *mut Compressor,
+) {
+    catch_panic(|| unsafe { (*compressor).reset() })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_outAddress(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *const Compressor,
+) -> *const u8 {
+    catch_panic(|| unsafe { (*compressor).out.as_ptr() })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_clearOut(
+    _env: JNIEnv,
+    _class: JClass,
+    compressor: *mut Compressor,
+) {
+    catch_panic(|| unsafe { (*compressor).out.clear() })
+}
+
+/// Returns null when the buffer does not end with a seek table, or the table has a frame
+/// larger than `MAX_BLOCK_SIZE`.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_createReader(
+    _env: JNIEnv,
+    _class: JClass,
+    lo: *const u8,
+    size: jlong,
+) -> *mut Reader<'static> {
+    catch_panic(|| match Reader::new(unsafe { mem::slice(lo, size) }) {
+        Some(reader) => Box::into_raw(Box::new(reader)),
+        None => std::ptr::null_mut(),
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_destroyReader(
+    _env: JNIEnv,
+    _class: JClass,
+    reader: *mut Reader,
+) {
+    catch_panic(|| {
+        if !reader.is_null() {
+            drop(unsafe { Box::from_raw(reader) });
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_decompressedSize(
+    _env: JNIEnv,
+    _class: JClass,
+    reader: *const Reader,
+) -> jlong {
+    catch_panic(|| unsafe { (*reader).size() as jlong })
+}
+
+/// Returns the number of bytes read, or -1 when a frame is corrupt or fails its checksum.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_CompressionNative_read(
+    _env: JNIEnv,
+    _class: JClass,
+    reader: *mut Reader,
+    offset: jlong,
+    dst: *mut u8,
+    size: jlong,
+) -> jlong {
+    catch_panic(|| {
+        if offset < 0 {
+            return -1;
+        }
+        let reader = unsafe { &mut *reader };
+        match reader.read(offset as u64, unsafe { mem::slice_mut(dst, size) }) {
+            Some(n) => n as jlong,
+            None => -1,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(size: usize) -> Vec<u8> {
+        (0..size)
+            .map(|i| (i % 251) as u8 ^ (i / 1000) as u8)
+            .collect()
+    }
+
+    fn compress_stream(src: &[u8], block_size: usize, write_size: usize) -> Vec<u8> {
+        let mut compressor = Compressor::new(3, block_size).unwrap();
+        for chunk in src.chunks(write_size.max(1)) {
+            compressor.compress(chunk).unwrap();
+        }
+        compressor.finish().unwrap();
+        compressor.out
+    }
+
+    #[test]
+    fn seekable_round_trip() {
+        let src = input(10_000);
+        let out = compress_stream(&src, 1024, 700);
+        // The whole stream is valid zstd, readable without the seek table.
+        assert_eq!(zstd::decode_all(&out[..]).unwrap(), src);
+
+        let mut reader = Reader::new(&out).unwrap();
+        assert_eq!(reader.size(), src.len() as u64);
+        assert_eq!(reader.frames.len(), 10);
+        for (offset, size) in [
+            (0, 10_000),
+            (1000, 100),
+            (1023, 2),
+            (5000, 3000),
+            (9990, 100),
+        ] {
+            let mut dst = vec![0; size];
+            let n = reader.read(offset as u64, &mut dst).unwrap();
+            let expected = &src[offset..(offset + size).min(src.len())];
+            assert_eq!(&dst[..n], expected, "offset {offset}");
+        }
+        assert_eq!(reader.read(20_000, &mut [0; 8]), Some(0));
+    }
+
+    #[test]
+    fn empty_stream() {
+        let out = compress_stream(&[], 1024, 1);
+        let mut reader = Reader::new(&out).unwrap();
+        assert_eq!(reader.size(), 0);
+        assert_eq!(reader.read(0, &mut [0; 8]), Some(0));
+    }
+
+    #[test]
+    fn finished_compressor() {
+        let mut compressor = Compressor::new(3, 1024).unwrap();
+        compressor.compress(b"abc").unwrap();
+        compressor.finish().unwrap();
+        assert!(compressor.compress(b"def").is_err());
+        assert!(compressor.finish().is_err());
+
+        compressor.reset();
+        assert!(compressor.out.is_empty());
+        compressor.compress(b"def").unwrap();
+        compressor.finish().unwrap();
+        let mut reader = Reader::new(&compressor.out).unwrap();
+        let mut dst = [0; 3];
+        assert_eq!(reader.read(0, &mut dst), Some(3));
+        assert_eq!(&dst, b"def");
+    }
+
+    #[test]
+    fn corrupt_input() {
+        let src = input(4096);
+        let out = compress_stream(&src, 1024, 4096);
+        assert!(Reader::new(&out[..out.len() - 1]).is_none());
+        assert!(Reader::new(&[]).is_none());
+
+        // A seek table entry claiming an oversized frame is rejected before decoding.
+        let mut oversized = out.clone();
+        let entry = out.len() - SEEK_TABLE_FOOTER_SIZE - 8 + 4;
+        oversized[entry..entry + 4].copy_from_slice(&(MAX_BLOCK_SIZE as u32 + 1).to_le_bytes());
+        assert!(Reader::new(&oversized).is_none());
+
+        // A flipped byte in a frame fails to decode instead of returning garbage.
+        let mut flipped = out.clone();
+        flipped[20] ^= 0xff;
+        let mut reader = Reader::new(&flipped).unwrap();
+        assert_eq!(reader.read(0, &mut [0; 16]), None);
+        assert_eq!(reader.read(2048, &mut [0; 16]), Some(16));
+    }
+
+    fn round_trip(codec: Codec, level: i32) {
+        let src = input(5000);
+        let mut dst = vec![0; codec.bound(src.len()).unwrap()];
+        let size = compress_buffer(codec, level, &src, &mut dst).unwrap();
+        if level > 0 {
+            assert!(size < src.len(), "{codec:?}");
+        }
+        let mut back = vec![0; src.len()];
+        assert_eq!(
+            decompress_buffer(codec, &dst[..size], &mut back).unwrap(),
+            src.len()
+        );
+        assert_eq!(back, src);
+
+        assert!(compress_buffer(codec, level, &src, &mut [0; 8]).is_err());
+        assert!(decompress_buffer(codec, &dst[..size], &mut back[..100]).is_err());
+        assert!(decompress_buffer(codec, &dst[..size / 2], &mut back).is_err());
+
+        // Incompressible input fits the bound.
+        let noise: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let mut dst = vec![0; codec.bound(noise.len()).unwrap()];
+        let size = compress_buffer(codec, level, &noise, &mut dst).unwrap();
+        let mut back = vec![0; noise.len()];
+        assert_eq!(
+            decompress_buffer(codec, &dst[..size], &mut back).unwrap(),
+            noise.len()
+        );
+        assert_eq!(back, noise);
+
+        let mut dst = vec![0; codec.bound(0).unwrap()];
+        let size = compress_buffer(codec, level, &[], &mut dst).unwrap();
+        assert_eq!(decompress_buffer(codec, &dst[..size], &mut []).unwrap(), 0);
+    }
+
+    #[test]
+    fn zstd_round_trip() {
+        round_trip(Codec::Zstd, 3);
+        round_trip(Codec::Zstd, 19);
+        // The output is a single frame with a checksum.
+        let mut dst = vec![0; compress_bound(100)];
+        let size = compress_buffer(Codec::Zstd, 3, &input(100), &mut dst).unwrap();
+        assert_eq!(zstd::decode_all(&dst[..size]).unwrap(), input(100));
+        assert!(compress_buffer(Codec::Zstd, 100, b"abc", &mut dst).is_err());
+        assert!(Compressor::new(100, 1024).is_err());
+    }
+
+    #[test]
+    fn lz4_raw_round_trip() {
+        round_trip(Codec::Lz4Raw, 1);
+    }
+
+    #[test]
+    fn snappy_round_trip() {
+        round_trip(Codec::Snappy, 1);
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        for level in [0, 1, 6, 9] {
+            round_trip(Codec::Gzip, level);
+        }
+        let mut dst = vec![0; 100];
+        assert!(compress_buffer(Codec::Gzip, 10, b"abc", &mut dst).is_err());
+        assert!(compress_buffer(Codec::Gzip, -1, b"abc", &mut dst).is_err());
+    }
+
+    #[test]
+    fn codec_ids() {
+        assert_eq!(Codec::from_id(CODEC_SNAPPY), Some(Codec::Snappy));
+        assert_eq!(Codec::from_id(CODEC_GZIP), Some(Codec::Gzip));
+        assert_eq!(Codec::from_id(CODEC_ZSTD), Some(Codec::Zstd));
+        assert_eq!(Codec::from_id(CODEC_LZ4_RAW), Some(Codec::Lz4Raw));
+        // Uncompressed, LZO, Brotli and framed LZ4 are not supported.
+        for id in [-1, 0, 3, 4, 5, 8] {
+            assert_eq!(Codec::from_id(id), None);
+            let bound = Java_io_questdb_std_CompressionNative_compressBufferBound(
+                crate::test_env(),
+                JClass::default(),
+                id,
+                10,
+            );
+            assert_eq!(bound, -1);
+        }
+        let mut dst = [0u8; 64];
+        let size = Java_io_questdb_std_CompressionNative_compressBuffer(
+            crate::test_env(),
+            JClass::default(),
+            0,
+            0,
+            b"abc".as_ptr(),
+            3,
+            dst.as_mut_ptr(),
+            dst.len() as jlong,
+        );
+        assert_eq!(size, -1);
+    }
+}
@@ -0,0 +1,139 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Panic diagnostics. A panic must not unwind into the JVM, so every JNI export runs its body
+//! in `catch_panic()`, which returns a failure value instead. Exports returning a long return
+//! [`PANIC_RESULT`], which no export returns otherwise. Exports returning nothing, a pointer or
+//! a boolean cannot tell a panic from their other results, so only kernels writing outputs
+//! return a long status; after the others Java has to poll `Os.rustLastPanic()`. The hook
+//! reports the panic once, through the native log when it is enabled and to stderr
+//! otherwise, and keeps the last report for Java to retrieve.
+
+use std::backtrace::Backtrace;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Mutex, Once};
+
+use jni::objects::JClass;
+use jni::sys::{jlong, jstring};
+use jni::JNIEnv;
+
+/// `Os.RUST_PANIC`
+pub const PANIC_RESULT: jlong = jlong::MIN;
+
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            let thread = std::thread::current();
+            let report = format!(
+                "thread '{}' {info}\n{}",
+                thread.name().unwrap_or("<unnamed>"),
+                Backtrace::force_capture()
+            );
+            if log::log_enabled!(log::Level::Error) {
+                log::error!("{report}");
+            } else {
+                eprintln!("{report}");
+            }
+            *LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+        }));
+    });
+}
+
+/// Value returned by an export whose body panicked.
+pub trait PanicResult {
+    fn on_panic() -> Self;
+}
+
+impl PanicResult for () {
+    fn on_panic() -> Self {}
+}
+
+impl PanicResult for bool {
+    fn on_panic() -> Self {
+        false
+    }
+}
+
+impl PanicResult for jlong {
+    fn on_panic() -> Self {
+        PANIC_RESULT
+    }
+}
+
+impl<T> PanicResult for *const T {
+    fn on_panic() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> PanicResult for *mut T {
+    fn on_panic() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+/// Runs the body of a JNI export, returning `R::on_panic()` when it panics. The panic is
+/// reported by the hook.
+pub fn catch_panic<R: PanicResult>(f: impl FnOnce() -> R) -> R {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| R::on_panic())
+}
+
+/// Returns the report of the last panic, or null when there was none.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Os_rustLastPanic(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_panic(|| {
+        let last = LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner());
+        match last.as_deref().map(|report| env.new_string(report)) {
+            Some(Ok(report)) => report.into_raw(),
+            _ => std::ptr::null_mut(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_returns_failure_value() {
+        install_panic_hook();
+        assert_eq!(
+            catch_panic(|| -> jlong { panic!("panic in test") }),
+            PANIC_RESULT
+        );
+        let report = LAST_PANIC.lock().unwrap().clone().unwrap();
+        assert!(report.contains("panic in test"), "{report}");
+        assert!(report.contains("src/diagnostics.rs"), "{report}");
+
+        assert!(catch_panic(|| -> *mut u8 { panic!("panic in test") }).is_null());
+        assert!(!catch_panic(|| -> bool { panic!("panic in test") }));
+        assert_eq!(catch_panic(|| 42 as jlong), 42);
+    }
+}
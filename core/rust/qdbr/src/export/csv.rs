@@ -0,0 +1,302 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! RFC 4180 CSV: CRLF line endings, fields quoted only when they contain the delimiter, a
+//! quote or a line break. Nulls are empty fields.
+
+use jni::objects::JClass;
+use jni::sys::{jbyte, jint, jlong};
+use jni::JNIEnv;
+
+use super::{append, columns, ExportBuffer};
+use crate::column::{Column, ColumnDesc, ColumnError, Value};
+use crate::diagnostics::catch_panic;
+use crate::text::format;
+
+fn push_field(out: &mut Vec<u8>, field: &[u8], delimiter: u8) {
+    if !field
+        .iter()
+        .any(|&b| b == delimiter || matches!(b, b'"' | b'\r' | b'\n'))
+    {
+        out.extend_from_slice(field);
+        return;
+    }
+    out.push(b'"');
+    for &b in field {
+        if b == b'"' {
+            out.push(b'"');
+        }
+        out.push(b);
+    }
+    out.push(b'"');
+}
+
+fn push_value(out: &mut Vec<u8>, scratch: &mut Vec<u8>, value: Value, delimiter: u8) {
+    match value {
+        Value::Utf8(v) => push_field(out, v, delimiter),
+        Value::Char(c) => {
+            scratch.clear();
+            format::push_utf16(scratch, &c.to_le_bytes());
+            push_field(out, scratch, delimiter);
+        }
+        Value::Utf16(v) => {
+            scratch.clear();
+            format::push_utf16(scratch, v);
+            push_field(out, scratch, delimiter);
+        }
+        // The remaining values never contain quotes or line breaks, only a delimiter such as
+        // '.' or '-' makes them need quoting.
+        _ => {
+            let at = out.len();
+            match value {
+                Value::Null => {}
+                Value::Boolean(v) => out.extend_from_slice(if v { b"true" } else { b"false" }),
+                Value::Long(v) => format::push_i64(out, v),
+                Value::Float(v) if v.is_finite() => format::push_f32(out, v),
+                Value::Double(v) if v.is_finite() => format::push_f64(out, v),
+                Value::Float(_) | Value::Double(_) => {}
+                Value::Date(v) => format::push_date(out, v),
+                Value::Timestamp(v) => format::push_timestamp(out, v),
+                Value::Ipv4(v) => format::push_ipv4(out, v),
+                Value::GeoHash(v, bits) => format::push_geohash(out, v, bits),
+                Value::Uuid(lo, hi) => format::push_uuid(out, lo, hi),
+                Value::Long256(v) => format::push_long256(out, &v),
+                _ => unreachable!(),
+            }
+            if out[at..].contains(&delimiter) {
+                out.insert(at, b'"');
+                out.push(b'"');
+            }
+        }
+    }
+}
+
+fn write_rows(
+    buffer: &mut ExportBuffer,
+    columns: &[Column],
+    row_lo: usize,
+    row_hi: usize,
+    delimiter: u8,
+) -> Result<(), ColumnError> {
+    for row in row_lo..row_hi {
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                buffer.bytes.push(delimiter);
+            }
+            let value = column.value(row)?;
+            push_value(&mut buffer.bytes, &mut buffer.scratch, value, delimiter);
+        }
+        buffer.bytes.extend_from_slice(b"\r\n");
+    }
+    Ok(())
+}
+
+/// Appends the header line with the column names. Returns the buffer size, or a negative
+/// error code leaving the buffer unchanged.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writeCsvHeader(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+    p_columns: *const ColumnDesc,
+    column_count: jint,
+    delimiter: jbyte,
+) -> jlong {
+    catch_panic(|| {
+        let columns = match unsafe { columns(p_columns, column_count as jlong, 0, 0) } {
+            Ok(columns) => columns,
+            Err(e) => return e.code(),
+        };
+        let buffer = unsafe { &mut *buffer };
+        append(buffer, |buffer| {
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    buffer.bytes.push(delimiter as u8);
+                }
+                push_field(&mut buffer.bytes, column.name, delimiter as u8);
+            }
+            buffer.bytes.extend_from_slice(b"\r\n");
+            Ok(())
+        })
+    })
+}
+
+/// Appends rows `[row_lo, row_hi)` as CSV lines. Returns the buffer size, or a negative error
+/// code leaving the buffer unchanged.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writeCsv(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+    p_columns: *const ColumnDesc,
+    column_count: jint,
+    row_lo: jlong,
+    row_hi: jlong,
+    delimiter: jbyte,
+) -> jlong {
+    catch_panic(|| {
+        let columns = match unsafe { columns(p_columns, column_count as jlong, row_lo, row_hi) } {
+            Ok(columns) => columns,
+            Err(e) => return e.code(),
+        };
+        let buffer = unsafe { &mut *buffer };
+        append(buffer, |buffer| {
+            write_rows(
+                buffer,
+                &columns,
+                row_lo as usize,
+                row_hi as usize,
+                delimiter as u8,
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::{string_column, varchar_column};
+    use crate::column_type::{self as ct, INT_NULL, LONG_NULL};
+
+    fn le<const N: usize, T>(values: &[T], to_le: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(to_le).collect()
+    }
+
+    fn csv(descs: &[ColumnDesc], delimiter: u8) -> String {
+        let columns: Vec<Column> = descs
+            .iter()
+            .map(|d| unsafe { Column::new(d) }.unwrap())
+            .collect();
+        let mut buffer = ExportBuffer::default();
+        let row_count = columns[0].row_count();
+        write_rows(&mut buffer, &columns, 0, row_count, delimiter).unwrap();
+        String::from_utf8(buffer.bytes).unwrap()
+    }
+
+    #[test]
+    fn nulls_of_every_type() {
+        let geohash = |tag: i32, bits: i32| tag | bits << 8;
+        let symbol_chars: Vec<u8> = [1i32.to_le_bytes(), [b'a', 0, 0, 0]].concat();
+        let symbol_offsets = [0i64];
+        let symbols = le(&[ct::SYMBOL_NULL, 0], |v| v.to_le_bytes());
+        let (string_data, string_aux) = string_column(&[None, Some("s")]);
+        let (varchar_data, varchar_aux) = varchar_column(&[None, Some("v")]);
+        let fixed: Vec<(i32, Vec<u8>, &str)> = vec![
+            (ct::CHAR, le(&[0u16, 65], |v| v.to_le_bytes()), "A"),
+            (ct::INT, le(&[INT_NULL, 1], |v| v.to_le_bytes()), "1"),
+            (ct::LONG, le(&[LONG_NULL, 2], |v| v.to_le_bytes()), "2"),
+            (
+                ct::DATE,
+                le(&[LONG_NULL, 0], |v| v.to_le_bytes()),
+                "1970-01-01T00:00:00.000Z",
+            ),
+            (
+                ct::TIMESTAMP,
+                le(&[LONG_NULL, 0], |v| v.to_le_bytes()),
+                "1970-01-01T00:00:00.000000Z",
+            ),
+            (ct::FLOAT, le(&[f32::NAN, 1.5], |v| v.to_le_bytes()), "1.5"),
+            (ct::DOUBLE, le(&[f64::NAN, 2.5], |v| v.to_le_bytes()), "2.5"),
+            (
+                ct::IPV4,
+                le(&[0u32, 0x7f000001], |v| v.to_le_bytes()),
+                "127.0.0.1",
+            ),
+            (geohash(ct::GEOBYTE, 5), vec![0xff, 9], "9"),
+            (
+                geohash(ct::GEOSHORT, 3),
+                le(&[-1i16, 5], |v| v.to_le_bytes()),
+                "101",
+            ),
+            (
+                geohash(ct::GEOINT, 20),
+                le(&[-1i32, 0], |v| v.to_le_bytes()),
+                "0000",
+            ),
+            (
+                geohash(ct::GEOLONG, 60),
+                le(&[-1i64, 0], |v| v.to_le_bytes()),
+                "000000000000",
+            ),
+            (
+                ct::UUID,
+                le(&[LONG_NULL, LONG_NULL, 1, 2], |v| v.to_le_bytes()),
+                "00000000-0000-0002-0000-000000000001",
+            ),
+            (
+                ct::LONG256,
+                le(
+                    &[LONG_NULL, LONG_NULL, LONG_NULL, LONG_NULL, 1, 0, 0, 0],
+                    |v| v.to_le_bytes(),
+                ),
+                "0x01",
+            ),
+        ];
+        let mut cases: Vec<(ColumnDesc, &str)> = fixed
+            .iter()
+            .map(|(column_type, data, value)| (ColumnDesc::of(*column_type, data, &[]), *value))
+            .collect();
+        cases.extend([
+            (
+                ColumnDesc {
+                    symbol_offsets: symbol_offsets.as_ptr(),
+                    symbol_count: 1,
+                    symbol_chars: symbol_chars.as_ptr(),
+                    symbol_chars_size: symbol_chars.len() as i64,
+                    ..ColumnDesc::of(ct::SYMBOL, &symbols, &[])
+                },
+                "a",
+            ),
+            (ColumnDesc::of(ct::STRING, &string_data, &string_aux), "s"),
+            (
+                ColumnDesc::of(ct::VARCHAR, &varchar_data, &varchar_aux),
+                "v",
+            ),
+        ]);
+        for (desc, value) in &cases {
+            let tag = ct::tag(desc.column_type);
+            assert_eq!(
+                csv(&[*desc], b','),
+                format!("\r\n{value}\r\n"),
+                "type {tag}"
+            );
+        }
+        // BOOLEAN, BYTE and SHORT have no null.
+        let desc = ColumnDesc::of(ct::BOOLEAN, &[0, 1], &[]);
+        assert_eq!(csv(&[desc], b','), "false\r\ntrue\r\n");
+        let desc = ColumnDesc::of(ct::BYTE, &[0], &[]);
+        assert_eq!(csv(&[desc], b','), "0\r\n");
+        // A null between other fields is an empty field.
+        let descs: Vec<ColumnDesc> = cases.iter().map(|(desc, _)| *desc).collect();
+        let values: Vec<&str> = cases.iter().map(|(_, value)| *value).collect();
+        assert_eq!(
+            csv(&descs, b','),
+            format!(
+                "{}\r\n{}\r\n",
+                ",".repeat(cases.len() - 1),
+                values.join(",")
+            )
+        );
+    }
+}
@@ -0,0 +1,132 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Native encoders for exporting column data. Each encoder appends whole rows to an
+//! [`ExportBuffer`] owned by Rust, which Java drains between calls.
+
+mod csv;
+mod ndjson;
+mod pg_copy;
+
+use jni::objects::JClass;
+use jni::sys::jlong;
+use jni::JNIEnv;
+
+use crate::column::{Column, ColumnDesc, ColumnError};
+use crate::diagnostics::catch_panic;
+use crate::mem;
+
+#[derive(Default)]
+pub struct ExportBuffer {
+    bytes: Vec<u8>,
+    /// Reused for values that need transcoding before they are escaped.
+    scratch: Vec<u8>,
+}
+
+/// Resolves the column descriptions and checks that every column holds `row_hi` rows.
+///
+/// # Safety
+/// `columns` must point at `column_count` descriptions whose buffers are valid for `'a`.
+unsafe fn columns<'a>(
+    columns: *const ColumnDesc,
+    column_count: jlong,
+    row_lo: jlong,
+    row_hi: jlong,
+) -> Result<Vec<Column<'a>>, ColumnError> {
+    if column_count < 0 || row_lo < 0 || row_hi < row_lo {
+        return Err(ColumnError::Corrupt);
+    }
+    let columns = mem::slice(columns, column_count)
+        .iter()
+        .map(|desc| Column::new(desc))
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.iter().any(|c| c.row_count() < row_hi as usize) {
+        return Err(ColumnError::Corrupt);
+    }
+    Ok(columns)
+}
+
+/// Runs `encode` over the buffer, returning the new buffer size, or the error code with the
+/// buffer rolled back to its previous size.
+fn append(
+    buffer: &mut ExportBuffer,
+    encode: impl FnOnce(&mut ExportBuffer) -> Result<(), ColumnError>,
+) -> jlong {
+    let size = buffer.bytes.len();
+    match encode(buffer) {
+        Ok(()) => buffer.bytes.len() as jlong,
+        Err(e) => {
+            buffer.bytes.truncate(size);
+            e.code()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_createBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+) -> *mut ExportBuffer {
+    catch_panic(|| Box::into_raw(Box::default()))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_destroyBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+) {
+    catch_panic(|| {
+        if !buffer.is_null() {
+            drop(unsafe { Box::from_raw(buffer) });
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_bufferAddress(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *const ExportBuffer,
+) -> *const u8 {
+    catch_panic(|| unsafe { (*buffer).bytes.as_ptr() })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_bufferSize(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *const ExportBuffer,
+) -> jlong {
+    catch_panic(|| unsafe { (*buffer).bytes.len() as jlong })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_clearBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+) {
+    catch_panic(|| unsafe { (*buffer).bytes.clear() })
+}
@@ -0,0 +1,286 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! JSON Lines: one object per row, keyed by column name.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use super::{append, columns, ExportBuffer};
+use crate::column::{Column, ColumnDesc, ColumnError, Value};
+use crate::diagnostics::catch_panic;
+use crate::text::format;
+
+fn push_value(out: &mut Vec<u8>, scratch: &mut Vec<u8>, value: Value) {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Boolean(v) => out.extend_from_slice(if v { b"true" } else { b"false" }),
+        Value::Long(v) => format::push_i64(out, v),
+        Value::Float(v) if v.is_finite() => format::push_f32(out, v),
+        Value::Double(v) if v.is_finite() => format::push_f64(out, v),
+        Value::Float(_) | Value::Double(_) => out.extend_from_slice(b"null"),
+        Value::Utf8(v) => match std::str::from_utf8(v) {
+            Ok(_) => format::push_json_str(out, v),
+            Err(_) => format::push_json_str(out, String::from_utf8_lossy(v).as_bytes()),
+        },
+        Value::Char(c) => {
+            scratch.clear();
+            format::push_utf16(scratch, &c.to_le_bytes());
+            format::push_json_str(out, scratch);
+        }
+        Value::Utf16(v) => {
+            scratch.clear();
+            format::push_utf16(scratch, v);
+            format::push_json_str(out, scratch);
+        }
+        // The remaining values are quoted and never need escaping.
+        _ => {
+            out.push(b'"');
+            match value {
+                Value::Date(v) => format::push_date(out, v),
+                Value::Timestamp(v) => format::push_timestamp(out, v),
+                Value::Ipv4(v) => format::push_ipv4(out, v),
+                Value::GeoHash(v, bits) => format::push_geohash(out, v, bits),
+                Value::Uuid(lo, hi) => format::push_uuid(out, lo, hi),
+                Value::Long256(v) => format::push_long256(out, &v),
+                _ => unreachable!(),
+            }
+            out.push(b'"');
+        }
+    }
+}
+
+fn write_rows(
+    buffer: &mut ExportBuffer,
+    columns: &[Column],
+    row_lo: usize,
+    row_hi: usize,
+) -> Result<(), ColumnError> {
+    // Keys are the same for every row, escape them once.
+    let mut keys = Vec::with_capacity(columns.len());
+    for (i, column) in columns.iter().enumerate() {
+        let mut key = Vec::new();
+        key.push(if i == 0 { b'{' } else { b',' });
+        format::push_json_str(&mut key, String::from_utf8_lossy(column.name).as_bytes());
+        key.push(b':');
+        keys.push(key);
+    }
+    for row in row_lo..row_hi {
+        for (column, key) in columns.iter().zip(&keys) {
+            buffer.bytes.extend_from_slice(key);
+            push_value(&mut buffer.bytes, &mut buffer.scratch, column.value(row)?);
+        }
+        if columns.is_empty() {
+            buffer.bytes.push(b'{');
+        }
+        buffer.bytes.extend_from_slice(b"}\n");
+    }
+    Ok(())
+}
+
+/// Appends rows `[row_lo, row_hi)` as JSON Lines. Returns the buffer size, or a negative
+/// error code leaving the buffer unchanged.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writeNdjson(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+    p_columns: *const ColumnDesc,
+    column_count: jint,
+    row_lo: jlong,
+    row_hi: jlong,
+) -> jlong {
+    catch_panic(|| {
+        let columns = match unsafe { columns(p_columns, column_count as jlong, row_lo, row_hi) } {
+            Ok(columns) => columns,
+            Err(e) => return e.code(),
+        };
+        let buffer = unsafe { &mut *buffer };
+        append(buffer, |buffer| {
+            write_rows(buffer, &columns, row_lo as usize, row_hi as usize)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::{string_column, varchar_column};
+    use crate::column_type::{self as ct, INT_NULL, LONG_NULL};
+
+    fn le<const N: usize, T>(values: &[T], to_le: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(to_le).collect()
+    }
+
+    fn named(name: &str, desc: ColumnDesc) -> ColumnDesc {
+        ColumnDesc {
+            name: name.as_ptr(),
+            name_size: name.len() as i32,
+            ..desc
+        }
+    }
+
+    fn ndjson(descs: &[ColumnDesc], row_count: usize) -> String {
+        let columns: Vec<Column> = descs
+            .iter()
+            .map(|d| unsafe { Column::new(d) }.unwrap())
+            .collect();
+        let mut buffer = ExportBuffer::default();
+        write_rows(&mut buffer, &columns, 0, row_count).unwrap();
+        String::from_utf8(buffer.bytes).unwrap()
+    }
+
+    /// Lines of a single column named `v`.
+    fn values(desc: ColumnDesc) -> Vec<String> {
+        let row_count = unsafe { Column::new(&desc) }.unwrap().row_count();
+        ndjson(&[named("v", desc)], row_count)
+            .lines()
+            .map(|line| {
+                line.strip_prefix(r#"{"v":"#)
+                    .and_then(|line| line.strip_suffix('}'))
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_and_keys() {
+        let ints = le(&[1i32, INT_NULL], |v| v.to_le_bytes());
+        let (data, aux) = varchar_column(&[Some("x"), None]);
+        let descs = [
+            named("a", ColumnDesc::of(ct::INT, &ints, &[])),
+            named("b \"q\"\n", ColumnDesc::of(ct::VARCHAR, &data, &aux)),
+        ];
+        assert_eq!(
+            ndjson(&descs, 2),
+            "{\"a\":1,\"b \\\"q\\\"\\n\":\"x\"}\n{\"a\":null,\"b \\\"q\\\"\\n\":null}\n"
+        );
+        assert_eq!(ndjson(&[], 2), "{}\n{}\n");
+    }
+
+    #[test]
+    fn nulls() {
+        let data = le(&[LONG_NULL, 1], |v| v.to_le_bytes());
+        assert_eq!(values(ColumnDesc::of(ct::LONG, &data, &[])), ["null", "1"]);
+        let data = le(
+            &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e16, 1e-7, 0.5],
+            |v| v.to_le_bytes(),
+        );
+        assert_eq!(
+            values(ColumnDesc::of(ct::DOUBLE, &data, &[])),
+            ["null", "null", "null", "1.0E16", "1.0E-7", "0.5"]
+        );
+        let data = le(&[f32::NAN, f32::INFINITY, 1e7], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::FLOAT, &data, &[])),
+            ["null", "null", "1.0E7"]
+        );
+        let (data, aux) = string_column(&[None, Some("")]);
+        assert_eq!(
+            values(ColumnDesc::of(ct::STRING, &data, &aux)),
+            ["null", r#""""#]
+        );
+        let data = le(&[0u16], |v| v.to_le_bytes());
+        assert_eq!(values(ColumnDesc::of(ct::CHAR, &data, &[])), ["null"]);
+        let data = le(&[LONG_NULL, LONG_NULL], |v| v.to_le_bytes());
+        assert_eq!(values(ColumnDesc::of(ct::UUID, &data, &[])), ["null"]);
+        let data = le(&[0u32], |v| v.to_le_bytes());
+        assert_eq!(values(ColumnDesc::of(ct::IPV4, &data, &[])), ["null"]);
+    }
+
+    #[test]
+    fn quoted_values() {
+        let data = le(&[0i64, -1], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::TIMESTAMP, &data, &[])),
+            [
+                r#""1970-01-01T00:00:00.000000Z""#,
+                r#""1969-12-31T23:59:59.999999Z""#
+            ]
+        );
+        let data = le(&[LONG_NULL, 951_782_400_000], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::DATE, &data, &[])),
+            ["null", r#""2000-02-29T00:00:00.000Z""#]
+        );
+        let data = le(&[0x7f000001u32], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::IPV4, &data, &[])),
+            [r#""127.0.0.1""#]
+        );
+        let data = le(&[1i64, 2], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::UUID, &data, &[])),
+            [r#""00000000-0000-0002-0000-000000000001""#]
+        );
+        let data = le(&[5i16], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::GEOSHORT | 3 << 8, &data, &[])),
+            [r#""101""#]
+        );
+        let desc = ColumnDesc::of(ct::BOOLEAN, &[1, 0], &[]);
+        assert_eq!(values(desc), ["true", "false"]);
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let (data, aux) = string_column(&[Some("a\"b\\c\nd\u{1}é")]);
+        assert_eq!(
+            values(ColumnDesc::of(ct::STRING, &data, &aux)),
+            [r#""a\"b\\c\nd\u0001é""#]
+        );
+        let (data, aux) =
+            varchar_column(&[Some("tab\there"), Some("a longer value with \"quotes\"")]);
+        assert_eq!(
+            values(ColumnDesc::of(ct::VARCHAR, &data, &aux)),
+            [r#""tab\there""#, r#""a longer value with \"quotes\"""#]
+        );
+        let data = le(&[u16::from(b'"')], |v| v.to_le_bytes());
+        assert_eq!(values(ColumnDesc::of(ct::CHAR, &data, &[])), [r#""\"""#]);
+    }
+
+    #[test]
+    fn invalid_text_is_replaced() {
+        // Invalid UTF-8 in a VARCHAR, an unpaired surrogate in a STRING.
+        let (_, aux) = varchar_column(&[Some("ab")]);
+        let mut aux = aux;
+        aux[1..3].copy_from_slice(&[0xff, b'"']);
+        assert_eq!(
+            values(ColumnDesc::of(ct::VARCHAR, &[], &aux)),
+            ["\"\u{fffd}\\\"\""]
+        );
+        let data = [
+            le(&[2i32], |v| v.to_le_bytes()),
+            le(&[0xd800u16, 0x61], |v| v.to_le_bytes()),
+        ]
+        .concat();
+        let aux = le(&[0i64], |v| v.to_le_bytes());
+        assert_eq!(
+            values(ColumnDesc::of(ct::STRING, &data, &aux)),
+            ["\"\u{fffd}a\""]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! PostgreSQL binary `COPY` format. Types are mapped the way the PG wire protocol sends them
+//! in binary: BYTE and SHORT as int2, DATE and TIMESTAMP as timestamp, CHAR, STRING, SYMBOL,
+//! VARCHAR, IPv4, LONG256 and geohashes as text.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use super::{append, columns, ExportBuffer};
+use crate::column::{Column, ColumnDesc, ColumnError, Value};
+use crate::column_type as ct;
+use crate::diagnostics::catch_panic;
+use crate::text::format;
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+/// PG timestamps count from 2000-01-01.
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+fn push_field(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Writes a text field whose length is only known after formatting.
+fn push_text_field(out: &mut Vec<u8>, format: impl FnOnce(&mut Vec<u8>)) {
+    let len_at = out.len();
+    out.extend_from_slice(&[0; 4]);
+    format(out);
+    let len = (out.len() - len_at - 4) as i32;
+    out[len_at..len_at + 4].copy_from_slice(&len.to_be_bytes());
+}
+
+fn push_value(out: &mut Vec<u8>, tag: i32, value: Value) {
+    match value {
+        Value::Null => out.extend_from_slice(&(-1i32).to_be_bytes()),
+        Value::Boolean(v) => push_field(out, &[v as u8]),
+        Value::Long(v) => match tag {
+            ct::BYTE | ct::SHORT => push_field(out, &(v as i16).to_be_bytes()),
+            ct::INT => push_field(out, &(v as i32).to_be_bytes()),
+            _ => push_field(out, &v.to_be_bytes()),
+        },
+        Value::Date(v) => push_field(
+            out,
+            &v.wrapping_mul(1000)
+                .wrapping_sub(PG_EPOCH_OFFSET_MICROS)
+                .to_be_bytes(),
+        ),
+        Value::Timestamp(v) => {
+            push_field(out, &v.wrapping_sub(PG_EPOCH_OFFSET_MICROS).to_be_bytes())
+        }
+        Value::Float(v) => push_field(out, &v.to_be_bytes()),
+        Value::Double(v) => push_field(out, &v.to_be_bytes()),
+        Value::Uuid(lo, hi) => {
+            out.extend_from_slice(&16i32.to_be_bytes());
+            out.extend_from_slice(&hi.to_be_bytes());
+            out.extend_from_slice(&lo.to_be_bytes());
+        }
+        Value::Char(c) => push_text_field(out, |out| format::push_utf16(out, &c.to_le_bytes())),
+        Value::Utf16(v) => push_text_field(out, |out| format::push_utf16(out, v)),
+        Value::Utf8(v) => push_field(out, v),
+        Value::Ipv4(v) => push_text_field(out, |out| format::push_ipv4(out, v)),
+        Value::GeoHash(v, bits) => push_text_field(out, |out| format::push_geohash(out, v, bits)),
+        Value::Long256(v) => push_text_field(out, |out| format::push_long256(out, &v)),
+    }
+}
+
+fn write_rows(
+    buffer: &mut ExportBuffer,
+    columns: &[Column],
+    row_lo: usize,
+    row_hi: usize,
+) -> Result<(), ColumnError> {
+    let field_count = (columns.len() as i16).to_be_bytes();
+    for row in row_lo..row_hi {
+        buffer.bytes.extend_from_slice(&field_count);
+        for column in columns {
+            push_value(&mut buffer.bytes, column.tag(), column.value(row)?);
+        }
+    }
+    Ok(())
+}
+
+/// Appends the file header: signature, flags and an empty header extension.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writePgCopyHeader(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+) -> jlong {
+    catch_panic(|| {
+        let bytes = unsafe { &mut (*buffer).bytes };
+        bytes.extend_from_slice(SIGNATURE);
+        bytes.extend_from_slice(&0i32.to_be_bytes());
+        bytes.extend_from_slice(&0i32.to_be_bytes());
+        bytes.len() as jlong
+    })
+}
+
+/// Appends rows `[row_lo, row_hi)` as binary COPY tuples. Returns the buffer size, or a
+/// negative error code leaving the buffer unchanged.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writePgCopy(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+    p_columns: *const ColumnDesc,
+    column_count: jint,
+    row_lo: jlong,
+    row_hi: jlong,
+) -> jlong {
+    catch_panic(|| {
+        if column_count > i16::MAX as jint {
+            return ColumnError::UnsupportedType.code();
+        }
+        let columns = match unsafe { columns(p_columns, column_count as jlong, row_lo, row_hi) } {
+            Ok(columns) => columns,
+            Err(e) => return e.code(),
+        };
+        let buffer = unsafe { &mut *buffer };
+        append(buffer, |buffer| {
+            write_rows(buffer, &columns, row_lo as usize, row_hi as usize)
+        })
+    })
+}
+
+/// Appends the end of data marker.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_ExportNative_writePgCopyTrailer(
+    _env: JNIEnv,
+    _class: JClass,
+    buffer: *mut ExportBuffer,
+) -> jlong {
+    catch_panic(|| {
+        let bytes = unsafe { &mut (*buffer).bytes };
+        bytes.extend_from_slice(&(-1i16).to_be_bytes());
+        bytes.len() as jlong
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::{string_column, varchar_column};
+    use crate::column_type::{INT_NULL, LONG_NULL};
+
+    type Field = Option<Vec<u8>>;
+
+    fn le<const N: usize, T>(values: &[T], to_le: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(to_le).collect()
+    }
+
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> &'a [u8] {
+        let (head, tail) = bytes.split_at(n);
+        *bytes = tail;
+        head
+    }
+
+    /// Writes header, rows and trailer, and decodes the tuples back.
+    fn copy(descs: &[ColumnDesc]) -> Vec<Vec<Field>> {
+        let mut buffer = ExportBuffer::default();
+        let row_count = unsafe { Column::new(&descs[0]) }.unwrap().row_count() as jlong;
+        Java_io_questdb_cutlass_text_ExportNative_writePgCopyHeader(
+            crate::test_env(),
+            JClass::default(),
+            &mut buffer,
+        );
+        let size = Java_io_questdb_cutlass_text_ExportNative_writePgCopy(
+            crate::test_env(),
+            JClass::default(),
+            &mut buffer,
+            descs.as_ptr(),
+            descs.len() as jint,
+            0,
+            row_count,
+        );
+        assert_eq!(size, buffer.bytes.len() as jlong);
+        Java_io_questdb_cutlass_text_ExportNative_writePgCopyTrailer(
+            crate::test_env(),
+            JClass::default(),
+            &mut buffer,
+        );
+
+        let mut bytes = buffer.bytes.as_slice();
+        assert_eq!(take(&mut bytes, SIGNATURE.len()), SIGNATURE);
+        // Flags and header extension length.
+        assert_eq!(take(&mut bytes, 8), [0; 8]);
+        let mut rows = Vec::new();
+        loop {
+            let field_count = i16::from_be_bytes(take(&mut bytes, 2).try_into().unwrap());
+            if field_count == -1 {
+                break;
+            }
+            assert_eq!(field_count, descs.len() as i16);
+            let row = (0..field_count)
+                .map(|_| {
+                    let len = i32::from_be_bytes(take(&mut bytes, 4).try_into().unwrap());
+                    (len != -1).then(|| take(&mut bytes, len as usize).to_vec())
+                })
+                .collect();
+            rows.push(row);
+        }
+        assert!(bytes.is_empty());
+        assert_eq!(rows.len() as jlong, row_count);
+        rows
+    }
+
+    /// Values of a single column.
+    fn fields(desc: ColumnDesc) -> Vec<Field> {
+        copy(&[desc])
+            .into_iter()
+            .map(|mut row| row.remove(0))
+            .collect()
+    }
+
+    fn text(s: &str) -> Field {
+        Some(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn header_and_trailer() {
+        let data = le(&[1i32], |v| v.to_le_bytes());
+        assert_eq!(copy(&[ColumnDesc::of(ct::INT, &data, &[])]).len(), 1);
+        // No rows.
+        assert!(copy(&[ColumnDesc::of(ct::INT, &[], &[])]).is_empty());
+    }
+
+    #[test]
+    fn numbers() {
+        let desc = ColumnDesc::of(ct::BOOLEAN, &[0, 1], &[]);
+        assert_eq!(fields(desc), [Some(vec![0]), Some(vec![1])]);
+        let desc = ColumnDesc::of(ct::BYTE, &[0xfe], &[]);
+        assert_eq!(fields(desc), [Some((-2i16).to_be_bytes().to_vec())]);
+        let data = le(&[-300i16], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::SHORT, &data, &[]);
+        assert_eq!(fields(desc), [Some((-300i16).to_be_bytes().to_vec())]);
+        let data = le(&[INT_NULL, 7], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::INT, &data, &[]);
+        assert_eq!(fields(desc), [None, Some(7i32.to_be_bytes().to_vec())]);
+        let data = le(&[LONG_NULL, -5], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::LONG, &data, &[]);
+        assert_eq!(fields(desc), [None, Some((-5i64).to_be_bytes().to_vec())]);
+        let data = le(&[f32::NAN, 1.5], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::FLOAT, &data, &[]);
+        assert_eq!(fields(desc), [None, Some(1.5f32.to_be_bytes().to_vec())]);
+        let data = le(&[f64::NAN, -2.5], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::DOUBLE, &data, &[]);
+        assert_eq!(fields(desc), [None, Some((-2.5f64).to_be_bytes().to_vec())]);
+    }
+
+    #[test]
+    fn timestamps_count_from_pg_epoch() {
+        // 2000-01-01T00:00:00.001Z in millis.
+        let data = le(&[LONG_NULL, 946_684_800_001i64, 0], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::DATE, &data, &[]);
+        assert_eq!(
+            fields(desc),
+            [
+                None,
+                Some(1000i64.to_be_bytes().to_vec()),
+                Some((-PG_EPOCH_OFFSET_MICROS).to_be_bytes().to_vec())
+            ]
+        );
+        let data = le(&[LONG_NULL, PG_EPOCH_OFFSET_MICROS + 1, 0], |v| {
+            v.to_le_bytes()
+        });
+        let desc = ColumnDesc::of(ct::TIMESTAMP, &data, &[]);
+        assert_eq!(
+            fields(desc),
+            [
+                None,
+                Some(1i64.to_be_bytes().to_vec()),
+                Some((-PG_EPOCH_OFFSET_MICROS).to_be_bytes().to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn uuid_is_hi_then_lo() {
+        let data = le(&[LONG_NULL, LONG_NULL, 1, 2], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::UUID, &data, &[]);
+        assert_eq!(
+            fields(desc),
+            [
+                None,
+                Some([2i64.to_be_bytes(), 1i64.to_be_bytes()].concat())
+            ]
+        );
+    }
+
+    #[test]
+    fn text_types() {
+        let data = le(&[0u16, 0xe9], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::CHAR, &data, &[]);
+        assert_eq!(fields(desc), [None, text("é")]);
+        let (data, aux) = string_column(&[None, Some("s"), Some("")]);
+        let desc = ColumnDesc::of(ct::STRING, &data, &aux);
+        assert_eq!(fields(desc), [None, text("s"), text("")]);
+        let (data, aux) = varchar_column(&[None, Some("v"), Some("a longer varchar value")]);
+        let desc = ColumnDesc::of(ct::VARCHAR, &data, &aux);
+        assert_eq!(
+            fields(desc),
+            [None, text("v"), text("a longer varchar value")]
+        );
+        let data = le(&[0u32, 0x7f000001], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::IPV4, &data, &[]);
+        assert_eq!(fields(desc), [None, text("127.0.0.1")]);
+        let data = le(&[-1i16, 5], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::GEOSHORT | 3 << 8, &data, &[]);
+        assert_eq!(fields(desc), [None, text("101")]);
+        let data = le(&[-1i64, 0], |v| v.to_le_bytes());
+        let desc = ColumnDesc::of(ct::GEOLONG | 60 << 8, &data, &[]);
+        assert_eq!(fields(desc), [None, text("000000000000")]);
+        let data = le(
+            &[LONG_NULL, LONG_NULL, LONG_NULL, LONG_NULL, 1, 0, 0, 0],
+            |v| v.to_le_bytes(),
+        );
+        let desc = ColumnDesc::of(ct::LONG256, &data, &[]);
+        assert_eq!(fields(desc), [None, text("0x01")]);
+    }
+
+    #[test]
+    fn field_count() {
+        let ints = le(&[1i32, INT_NULL], |v| v.to_le_bytes());
+        let (data, aux) = string_column(&[None, Some("x")]);
+        let descs = [
+            ColumnDesc::of(ct::INT, &ints, &[]),
+            ColumnDesc::of(ct::STRING, &data, &aux),
+            ColumnDesc::of(ct::BYTE, &[3, 4], &[]),
+        ];
+        assert_eq!(
+            copy(&descs),
+            [
+                vec![
+                    Some(1i32.to_be_bytes().to_vec()),
+                    None,
+                    Some(3i16.to_be_bytes().to_vec())
+                ],
+                vec![None, text("x"), Some(4i16.to_be_bytes().to_vec())]
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_columns() {
+        let desc = ColumnDesc::of(ct::BYTE, &[1], &[]);
+        let mut buffer = ExportBuffer::default();
+        buffer.bytes.extend_from_slice(b"kept");
+        // The field count is an int2, the descriptors are not read.
+        let result = Java_io_questdb_cutlass_text_ExportNative_writePgCopy(
+            crate::test_env(),
+            JClass::default(),
+            &mut buffer,
+            &desc,
+            i16::MAX as jint + 1,
+            0,
+            1,
+        );
+        assert_eq!(result, ColumnError::UnsupportedType.code());
+        assert_eq!(buffer.bytes, b"kept");
+    }
+}
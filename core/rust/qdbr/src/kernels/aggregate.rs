@@ -0,0 +1,268 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::objects::JClass;
+use jni::sys::jlong;
+use jni::JNIEnv;
+
+use crate::column_type::LONG_NULL;
+use crate::diagnostics::catch_panic;
+use crate::mem::slice;
+
+/// Partial aggregates of a long buffer; `min` and `max` are `LONG_NULL` when `count` is zero.
+/// Partials of several buffers merge by adding counts and sums and folding min/max.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LongAggregates {
+    pub count: i64,
+    pub sum: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+/// Partial aggregates of the finite values of a double buffer; `min` and `max` are NaN when
+/// `count` is zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoubleAggregates {
+    pub count: i64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl LongAggregates {
+    pub const EMPTY: LongAggregates = LongAggregates {
+        count: 0,
+        sum: 0,
+        min: LONG_NULL,
+        max: LONG_NULL,
+    };
+
+    pub fn push(&mut self, v: i64) {
+        if v == LONG_NULL {
+            return;
+        }
+        if self.count == 0 || v < self.min {
+            self.min = v;
+        }
+        self.max = self.max.max(v);
+        self.sum = self.sum.wrapping_add(v);
+        self.count += 1;
+    }
+}
+
+impl DoubleAggregates {
+    pub const EMPTY: DoubleAggregates = DoubleAggregates {
+        count: 0,
+        sum: 0.0,
+        min: f64::NAN,
+        max: f64::NAN,
+    };
+
+    pub fn push(&mut self, v: f64) {
+        if !v.is_finite() {
+            return;
+        }
+        // f64::min/max return the non-NaN operand, which seeds the first value.
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        self.sum += v;
+        self.count += 1;
+    }
+}
+
+/// Aggregates non-null values; `LONG_NULL` rows are skipped. The sum wraps on overflow,
+/// matching `sum(long)` in SQL.
+pub fn aggregate_long(values: &[i64]) -> LongAggregates {
+    let mut count = 0i64;
+    let mut sum = 0i64;
+    // LONG_NULL is the smallest long, so it can never win max; min needs masking.
+    let mut min = i64::MAX;
+    let mut max = LONG_NULL;
+    for &v in values {
+        let not_null = v != LONG_NULL;
+        count += not_null as i64;
+        sum = sum.wrapping_add(if not_null { v } else { 0 });
+        min = min.min(if not_null { v } else { i64::MAX });
+        max = max.max(v);
+    }
+    if count == 0 {
+        min = LONG_NULL;
+    }
+    LongAggregates {
+        count,
+        sum,
+        min,
+        max,
+    }
+}
+
+/// Aggregates non-null values; NaN and infinite rows are skipped, as `Numbers.isFinite()`
+/// makes `sum(double)` and `count(double)` skip them. Summation is compensated (Kahan) to
+/// keep precision over large buffers. Once the sum of finite values overflows to an infinity,
+/// the compensation is dropped so that the sum stays infinite.
+pub fn aggregate_double(values: &[f64]) -> DoubleAggregates {
+    let mut count = 0i64;
+    let mut sum = 0f64;
+    let mut c = 0f64;
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    for &v in values {
+        if !v.is_finite() {
+            continue;
+        }
+        count += 1;
+        let y = v - c;
+        let t = sum + y;
+        // (inf - sum) - inf would be NaN and poison every following row.
+        c = if t.is_finite() { (t - sum) - y } else { 0.0 };
+        sum = t;
+        min = min.min(v);
+        max = max.max(v);
+    }
+    if count == 0 {
+        min = f64::NAN;
+        max = f64::NAN;
+    }
+    DoubleAggregates {
+        count,
+        sum,
+        min,
+        max,
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_aggregateLong(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i64,
+    row_count: jlong,
+    aggregates_out: *mut LongAggregates,
+) -> jlong {
+    catch_panic(|| {
+        let values = unsafe { slice(values, row_count) };
+        unsafe { aggregates_out.write_unaligned(aggregate_long(values)) };
+        0
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_aggregateDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const f64,
+    row_count: jlong,
+    aggregates_out: *mut DoubleAggregates,
+) -> jlong {
+    catch_panic(|| {
+        let values = unsafe { slice(values, row_count) };
+        unsafe { aggregates_out.write_unaligned(aggregate_double(values)) };
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_skips_nulls() {
+        let a = aggregate_long(&[3, LONG_NULL, -5, 10]);
+        assert_eq!((a.count, a.sum, a.min, a.max), (3, 8, -5, 10));
+        assert_eq!(aggregate_long(&[LONG_NULL]), LongAggregates::EMPTY);
+        assert_eq!(aggregate_long(&[]), LongAggregates::EMPTY);
+        assert_eq!(aggregate_long(&[i64::MAX, 1]).sum, i64::MIN);
+    }
+
+    #[test]
+    fn double_skips_nulls() {
+        let a = aggregate_double(&[1.5, f64::NAN, -2.0]);
+        assert_eq!((a.count, a.sum, a.min, a.max), (2, -0.5, -2.0, 1.5));
+        let empty = aggregate_double(&[f64::NAN]);
+        assert_eq!((empty.count, empty.sum), (0, 0.0));
+        assert!(empty.min.is_nan() && empty.max.is_nan());
+        assert_eq!(aggregate_double(&[]).count, 0);
+    }
+
+    #[test]
+    fn double_skips_infinities() {
+        let a = aggregate_double(&[f64::INFINITY, 1.0]);
+        assert_eq!((a.count, a.sum, a.min, a.max), (1, 1.0, 1.0, 1.0));
+        let a = aggregate_double(&[1.0, f64::NEG_INFINITY, 2.0]);
+        assert_eq!((a.count, a.sum, a.min, a.max), (2, 3.0, 1.0, 2.0));
+        let empty = aggregate_double(&[f64::INFINITY, f64::NEG_INFINITY, f64::NAN]);
+        assert_eq!((empty.count, empty.sum), (0, 0.0));
+        assert!(empty.min.is_nan() && empty.max.is_nan());
+        // A sum of finite values can still overflow.
+        let a = aggregate_double(&[f64::MAX, f64::MAX, 1.0]);
+        assert_eq!((a.count, a.sum), (3, f64::INFINITY));
+        assert_eq!(
+            aggregate_double(&[-f64::MAX, -f64::MAX]).sum,
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn push_matches_aggregate() {
+        let values = [f64::INFINITY, 2.5, f64::NAN, -1.0, f64::NEG_INFINITY, 4.0];
+        let mut a = DoubleAggregates::EMPTY;
+        values.iter().for_each(|&v| a.push(v));
+        assert_eq!(a, aggregate_double(&values));
+        let mut a = DoubleAggregates::EMPTY;
+        a.push(f64::INFINITY);
+        assert_eq!(a.count, 0);
+        assert!(a.min.is_nan() && a.max.is_nan());
+    }
+
+    #[test]
+    fn double_compensates() {
+        let mut values = vec![1.0; 10];
+        values.insert(0, 1e16);
+        assert_eq!(aggregate_double(&values).sum, 1e16 + 10.0);
+    }
+
+    #[test]
+    fn exports_return_zero() {
+        let mut long = LongAggregates::EMPTY;
+        let result = Java_io_questdb_std_Kernels_aggregateLong(
+            crate::test_env(),
+            JClass::default(),
+            [3i64, 4].as_ptr(),
+            2,
+            &mut long,
+        );
+        assert_eq!((result, long), (0, aggregate_long(&[3, 4])));
+        let mut double = DoubleAggregates::EMPTY;
+        let result = Java_io_questdb_std_Kernels_aggregateDouble(
+            crate::test_env(),
+            JClass::default(),
+            [1.5f64, 2.5].as_ptr(),
+            2,
+            &mut double,
+        );
+        assert_eq!((result, double), (0, aggregate_double(&[1.5, 2.5])));
+    }
+}
@@ -0,0 +1,306 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Comparison predicates against constants, producing row-selection bitmaps.
+//!
+//! Null handling follows QuestDB SQL: nulls never satisfy ordering comparisons or BETWEEN,
+//! `!=` selects them, and comparing with a null constant tests for null.
+//!
+//! Doubles are compared as the SQL double operators do instead, see [`compare_double`].
+
+use jni::objects::JClass;
+use jni::sys::{jdouble, jint, jlong};
+use jni::JNIEnv;
+
+use super::{bitmap_words, WORD_BITS};
+use crate::column_type::{INT_NULL, LONG_NULL};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+pub const OP_EQ: jint = 0;
+pub const OP_NE: jint = 1;
+pub const OP_LT: jint = 2;
+pub const OP_LE: jint = 3;
+pub const OP_GT: jint = 4;
+pub const OP_GE: jint = 5;
+/// Inclusive, with the bounds in either order.
+pub const OP_BETWEEN: jint = 6;
+
+/// `Numbers.DOUBLE_TOLERANCE`
+pub const DOUBLE_TOLERANCE: f64 = 0.0000000001;
+
+pub trait Nullable: Copy + PartialOrd {
+    fn is_null(self) -> bool;
+}
+
+impl Nullable for i32 {
+    fn is_null(self) -> bool {
+        self == INT_NULL
+    }
+}
+
+impl Nullable for i64 {
+    fn is_null(self) -> bool {
+        self == LONG_NULL
+    }
+}
+
+/// NaN and infinities, as `Numbers.isNull(double)`.
+impl Nullable for f64 {
+    fn is_null(self) -> bool {
+        !self.is_finite()
+    }
+}
+
+/// `Numbers.equals(double, double)`: nulls equal each other, other values are equal within
+/// [`DOUBLE_TOLERANCE`].
+pub fn double_equals(l: f64, r: f64) -> bool {
+    (l.is_null() && r.is_null()) || (l - r).abs() <= DOUBLE_TOLERANCE
+}
+
+fn select<T: Copy>(values: &[T], out: &mut [u64], pred: impl Fn(T) -> bool) -> usize {
+    let mut selected = 0;
+    for (chunk, word) in values.chunks(WORD_BITS).zip(out.iter_mut()) {
+        let mut bits = 0u64;
+        for (i, &v) in chunk.iter().enumerate() {
+            bits |= (pred(v) as u64) << i;
+        }
+        *word = bits;
+        selected += bits.count_ones() as usize;
+    }
+    selected
+}
+
+/// Selects rows where `value op a` holds (`a <= value <= b` for BETWEEN), one bit per row in
+/// `out`. Returns the number of selected rows, or None for an unknown operator.
+pub fn compare<T: Nullable>(values: &[T], op: jint, a: T, b: T, out: &mut [u64]) -> Option<usize> {
+    if a.is_null() || (op == OP_BETWEEN && b.is_null()) {
+        return match op {
+            OP_EQ => Some(select(values, out, |v| v.is_null())),
+            OP_NE => Some(select(values, out, |v| !v.is_null())),
+            OP_LT | OP_LE | OP_GT | OP_GE | OP_BETWEEN => Some(select(values, out, |_| false)),
+            _ => None,
+        };
+    }
+    // With a non-null constant, only the ordering comparisons need to exclude nulls.
+    Some(match op {
+        OP_EQ => select(values, out, |v| v == a),
+        OP_NE => select(values, out, |v| v != a),
+        OP_LT => select(values, out, |v| (v < a) & !v.is_null()),
+        OP_LE => select(values, out, |v| (v <= a) & !v.is_null()),
+        OP_GT => select(values, out, |v| (v > a) & !v.is_null()),
+        OP_GE => select(values, out, |v| (v >= a) & !v.is_null()),
+        OP_BETWEEN => {
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            select(values, out, |v| (v >= lo) & (v <= hi) & !v.is_null())
+        }
+        _ => return None,
+    })
+}
+
+/// [`compare`] with the semantics of the SQL double operators: `=` is [`double_equals`], `<`
+/// is `!equals && value < a` and `<=` is `equals || value < a`, likewise `>` and `>=`. So a
+/// null constant selects nulls with `=`, `<=` and `>=`, and infinities order as such against
+/// other constants. BETWEEN is `>=` the lower and `<=` the upper bound, a null bound selects
+/// nothing.
+pub fn compare_double(values: &[f64], op: jint, a: f64, b: f64, out: &mut [u64]) -> Option<usize> {
+    let eq = double_equals;
+    Some(match op {
+        OP_EQ => select(values, out, |v| eq(v, a)),
+        OP_NE => select(values, out, |v| !eq(v, a)),
+        OP_LT => select(values, out, |v| !eq(v, a) & (v < a)),
+        OP_LE => select(values, out, |v| eq(v, a) | (v < a)),
+        OP_GT => select(values, out, |v| !eq(v, a) & (v > a)),
+        OP_GE => select(values, out, |v| eq(v, a) | (v > a)),
+        OP_BETWEEN if a.is_null() || b.is_null() => select(values, out, |_| false),
+        OP_BETWEEN => {
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            select(values, out, |v| {
+                (eq(v, lo) | (v > lo)) & (eq(v, hi) | (v < hi))
+            })
+        }
+        _ => return None,
+    })
+}
+
+fn compare_jni<T: Nullable>(
+    values: *const T,
+    row_count: jlong,
+    op: jint,
+    a: T,
+    b: T,
+    bitmap_out: *mut u64,
+) -> jlong {
+    let words = bitmap_words(row_count as usize) as jlong;
+    let (values, out) = unsafe { (slice(values, row_count), slice_mut(bitmap_out, words)) };
+    compare(values, op, a, b, out).map_or(-1, |n| n as jlong)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_compareInt(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i32,
+    row_count: jlong,
+    op: jint,
+    a: jint,
+    b: jint,
+    bitmap_out: *mut u64,
+) -> jlong {
+    catch_panic(|| compare_jni(values, row_count, op, a, b, bitmap_out))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_compareLong(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i64,
+    row_count: jlong,
+    op: jint,
+    a: jlong,
+    b: jlong,
+    bitmap_out: *mut u64,
+) -> jlong {
+    catch_panic(|| compare_jni(values, row_count, op, a, b, bitmap_out))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_compareDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const f64,
+    row_count: jlong,
+    op: jint,
+    a: jdouble,
+    b: jdouble,
+    bitmap_out: *mut u64,
+) -> jlong {
+    catch_panic(|| {
+        let words = bitmap_words(row_count as usize) as jlong;
+        let (values, out) = unsafe { (slice(values, row_count), slice_mut(bitmap_out, words)) };
+        compare_double(values, op, a, b, out).map_or(-1, |n| n as jlong)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows<T: Nullable>(values: &[T], op: jint, a: T, b: T) -> Option<Vec<usize>> {
+        let mut out = vec![0; bitmap_words(values.len())];
+        let count = compare(values, op, a, b, &mut out)?;
+        let rows: Vec<usize> = (0..values.len())
+            .filter(|&row| out[row / WORD_BITS] & (1 << (row % WORD_BITS)) != 0)
+            .collect();
+        assert_eq!(rows.len(), count);
+        Some(rows)
+    }
+
+    #[test]
+    fn nulls_fail_ordering() {
+        let values = [1, INT_NULL, 3, 2];
+        assert_eq!(rows(&values, OP_EQ, 2, 0), Some(vec![3]));
+        assert_eq!(rows(&values, OP_NE, 2, 0), Some(vec![0, 1, 2]));
+        assert_eq!(rows(&values, OP_LT, 3, 0), Some(vec![0, 3]));
+        assert_eq!(rows(&values, OP_LE, 3, 0), Some(vec![0, 2, 3]));
+        assert_eq!(rows(&values, OP_GT, 1, 0), Some(vec![2, 3]));
+        assert_eq!(rows(&values, OP_GE, 1, 0), Some(vec![0, 2, 3]));
+        assert_eq!(rows(&values, OP_BETWEEN, 3, 2), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn null_constant_tests_for_null() {
+        let values = [LONG_NULL, 1, LONG_NULL];
+        assert_eq!(rows(&values, OP_EQ, LONG_NULL, 0), Some(vec![0, 2]));
+        assert_eq!(rows(&values, OP_NE, LONG_NULL, 0), Some(vec![1]));
+        assert_eq!(rows(&values, OP_LT, LONG_NULL, 0), Some(vec![]));
+        assert_eq!(rows(&values, OP_BETWEEN, 0, LONG_NULL), Some(vec![]));
+    }
+
+    fn double_rows(values: &[f64], op: jint, a: f64, b: f64) -> Option<Vec<usize>> {
+        let mut out = vec![0; bitmap_words(values.len())];
+        let count = compare_double(values, op, a, b, &mut out)?;
+        let rows: Vec<usize> = (0..values.len())
+            .filter(|&row| out[row / WORD_BITS] & (1 << (row % WORD_BITS)) != 0)
+            .collect();
+        assert_eq!(rows.len(), count);
+        Some(rows)
+    }
+
+    #[test]
+    fn doubles_within_tolerance_are_equal() {
+        let values = [1.0, 1.0 + 1e-11, 1.0 + 1e-9, 1.0 - 1e-11, 1.0 - 1e-9];
+        assert_eq!(double_rows(&values, OP_EQ, 1.0, 0.0), Some(vec![0, 1, 3]));
+        assert_eq!(double_rows(&values, OP_NE, 1.0, 0.0), Some(vec![2, 4]));
+        assert_eq!(double_rows(&values, OP_LT, 1.0, 0.0), Some(vec![4]));
+        assert_eq!(
+            double_rows(&values, OP_LE, 1.0, 0.0),
+            Some(vec![0, 1, 3, 4])
+        );
+        assert_eq!(double_rows(&values, OP_GT, 1.0, 0.0), Some(vec![2]));
+        assert_eq!(
+            double_rows(&values, OP_GE, 1.0, 0.0),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(
+            double_rows(&values, OP_BETWEEN, 1.0 + 5e-11, 2.0),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(double_rows(&values, 7, 1.0, 0.0), None);
+    }
+
+    #[test]
+    fn double_infinities_are_null() {
+        let (inf, nan) = (f64::INFINITY, f64::NAN);
+        let values = [nan, inf, -inf, 1.0];
+        // Nulls are equal to each other, whichever the constant.
+        assert_eq!(double_rows(&values, OP_EQ, nan, 0.0), Some(vec![0, 1, 2]));
+        assert_eq!(double_rows(&values, OP_EQ, -inf, 0.0), Some(vec![0, 1, 2]));
+        assert_eq!(double_rows(&values, OP_NE, inf, 0.0), Some(vec![3]));
+        assert_eq!(double_rows(&values, OP_EQ, 1.0, 0.0), Some(vec![3]));
+        assert_eq!(double_rows(&values, OP_NE, 1.0, 0.0), Some(vec![0, 1, 2]));
+        // Infinities order against other constants, NaN never does.
+        assert_eq!(double_rows(&values, OP_LT, 5.0, 0.0), Some(vec![2, 3]));
+        assert_eq!(double_rows(&values, OP_GT, 0.0, 0.0), Some(vec![1, 3]));
+        assert_eq!(double_rows(&values, OP_LT, nan, 0.0), Some(vec![]));
+        assert_eq!(double_rows(&values, OP_GE, nan, 0.0), Some(vec![0, 1, 2]));
+        assert_eq!(double_rows(&values, OP_BETWEEN, 0.0, 2.0), Some(vec![3]));
+        assert_eq!(double_rows(&values, OP_BETWEEN, -inf, 2.0), Some(vec![]));
+        assert_eq!(double_rows(&values, OP_BETWEEN, 2.0, nan), Some(vec![]));
+    }
+
+    #[test]
+    fn boundary_lengths_and_unknown_op() {
+        assert_eq!(rows::<i32>(&[], OP_EQ, 0, 0), Some(vec![]));
+        for len in [63, 64, 65, 128, 129] {
+            let values: Vec<i64> = (0..len).collect();
+            assert_eq!(
+                rows(&values, OP_GE, len - 1, 0),
+                Some(vec![len as usize - 1])
+            );
+        }
+        assert_eq!(rows(&[1], 7, 0, 0), None);
+        assert_eq!(rows(&[1], 7, INT_NULL, 0), None);
+    }
+}
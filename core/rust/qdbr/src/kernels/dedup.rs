@@ -0,0 +1,161 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Deduplication of rows sorted by timestamp, keeping the last row of each
+//! `(timestamp, keys...)` group.
+
+use std::collections::HashSet;
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use crate::column::{Column, ColumnDesc, ColumnError};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// Writes the indexes of rows to keep to `out` in ascending order and returns their count.
+/// `timestamps` must be sorted; rows with equal keys are only duplicates within a run of equal
+/// timestamps, where they need not be adjacent.
+pub fn dedup_rows(
+    timestamps: &[i64],
+    keys: &[Column],
+    out: &mut [i64],
+) -> Result<usize, ColumnError> {
+    let mut count = 0;
+    let mut row_keys: Vec<Vec<u8>> = Vec::new();
+    let mut lo = 0;
+    while lo < timestamps.len() {
+        let ts = timestamps[lo];
+        let hi = lo + timestamps[lo..].partition_point(|&t| t == ts);
+        if hi - lo == 1 || keys.is_empty() {
+            out[count] = hi as i64 - 1;
+            count += 1;
+            lo = hi;
+            continue;
+        }
+        row_keys.resize_with(hi - lo, Vec::new);
+        for (row, key) in (lo..hi).zip(row_keys.iter_mut()) {
+            key.clear();
+            for column in keys {
+                column.push_key(row, key)?;
+            }
+        }
+        // Walk the run backwards so the last row of each key wins, then restore row order.
+        let mut seen = HashSet::with_capacity(hi - lo);
+        let run_start = count;
+        for (row, key) in (lo..hi).zip(row_keys.iter()).rev() {
+            if seen.insert(key.as_slice()) {
+                out[count] = row as i64;
+                count += 1;
+            }
+        }
+        out[run_start..count].reverse();
+        lo = hi;
+    }
+    Ok(count)
+}
+
+/// Returns the number of rows written to `rows_out`, or a negative `ColumnError` code.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_dedupRows(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    p_key_columns: *const ColumnDesc,
+    key_column_count: jint,
+    rows_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        if row_count < 0 || key_column_count < 0 {
+            return ColumnError::Corrupt.code();
+        }
+        let keys = unsafe { slice(p_key_columns, key_column_count as jlong) }
+            .iter()
+            .map(|desc| unsafe { Column::new(desc) })
+            .collect::<Result<Vec<_>, _>>();
+        let keys = match keys {
+            Ok(keys) if keys.iter().all(|k| k.row_count() >= row_count as usize) => keys,
+            Ok(_) => return ColumnError::Corrupt.code(),
+            Err(e) => return e.code(),
+        };
+        let timestamps = unsafe { slice(timestamps, row_count) };
+        let out = unsafe { slice_mut(rows_out, row_count) };
+        match dedup_rows(timestamps, &keys, out) {
+            Ok(count) => count as jlong,
+            Err(e) => e.code(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::varchar_column;
+    use crate::column_type as ct;
+
+    fn dedup(timestamps: &[i64], descs: &[ColumnDesc]) -> Vec<i64> {
+        let keys: Vec<Column> = descs
+            .iter()
+            .map(|d| unsafe { Column::new(d) }.unwrap())
+            .collect();
+        let mut out = vec![0; timestamps.len()];
+        let count = dedup_rows(timestamps, &keys, &mut out).unwrap();
+        out.truncate(count);
+        out
+    }
+
+    #[test]
+    fn keeps_last_row_per_key_and_timestamp() {
+        let ts = [1, 1, 1, 2, 2, 3];
+        let ints: Vec<u8> = [7, 8, 7, 7, 7, 7i32]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let key = ColumnDesc::of(ct::INT, &ints, &[]);
+        assert_eq!(dedup(&ts, &[key]), [1, 2, 4, 5]);
+        assert_eq!(dedup(&ts, &[]), [2, 4, 5]);
+        assert!(dedup(&[], &[key]).is_empty());
+    }
+
+    #[test]
+    fn null_keys_are_equal() {
+        let ts = [1, 1, 1, 1];
+        let (data, aux) = varchar_column(&[None, Some("a"), None, Some("")]);
+        let key = ColumnDesc::of(ct::VARCHAR, &data, &aux);
+        assert_eq!(dedup(&ts, &[key]), [1, 2, 3]);
+    }
+
+    #[test]
+    fn short_key_column() {
+        let ints = 7i32.to_le_bytes();
+        let keys = [unsafe { Column::new(&ColumnDesc::of(ct::INT, &ints, &[])) }.unwrap()];
+        let mut out = [0; 2];
+        assert_eq!(
+            dedup_rows(&[1, 1], &keys, &mut out),
+            Err(ColumnError::Corrupt)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use super::{bitmap_words, WORD_BITS};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// Selects rows with `lo <= ts <= hi`, one bit per row in `out`. Returns the number of
+/// selected rows.
+pub fn select_interval(timestamps: &[i64], lo: i64, hi: i64, out: &mut [u64]) -> usize {
+    let mut selected = 0;
+    for (chunk, word) in timestamps.chunks(WORD_BITS).zip(out.iter_mut()) {
+        let mut bits = 0u64;
+        for (i, &ts) in chunk.iter().enumerate() {
+            bits |= (((ts >= lo) & (ts <= hi)) as u64) << i;
+        }
+        *word = bits;
+        selected += bits.count_ones() as usize;
+    }
+    selected
+}
+
+/// Same as [`select_interval`], additionally requiring the symbol key of the row to
+/// be equal to `key`.
+pub fn select_interval_symbol(
+    timestamps: &[i64],
+    keys: &[i32],
+    key: i32,
+    lo: i64,
+    hi: i64,
+    out: &mut [u64],
+) -> usize {
+    let mut selected = 0;
+    let chunks = timestamps.chunks(WORD_BITS).zip(keys.chunks(WORD_BITS));
+    for ((ts_chunk, key_chunk), word) in chunks.zip(out.iter_mut()) {
+        let mut bits = 0u64;
+        for (i, (&ts, &k)) in ts_chunk.iter().zip(key_chunk).enumerate() {
+            bits |= (((ts >= lo) & (ts <= hi) & (k == key)) as u64) << i;
+        }
+        *word = bits;
+        selected += bits.count_ones() as usize;
+    }
+    selected
+}
+
+/// Writes indexes of the rows selected in `bitmap` to `out`, in ascending order.
+/// Returns the number of indexes written.
+pub fn bitmap_to_rows(bitmap: &[u64], out: &mut [i64]) -> usize {
+    let mut n = 0;
+    for (w, &word) in bitmap.iter().enumerate() {
+        let mut bits = word;
+        while bits != 0 {
+            out[n] = (w * WORD_BITS) as i64 + bits.trailing_zeros() as i64;
+            n += 1;
+            bits &= bits - 1;
+        }
+    }
+    n
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_selectInterval(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    ts_lo: jlong,
+    ts_hi: jlong,
+    bitmap_out: *mut u64,
+) -> jlong {
+    catch_panic(|| {
+        let words = bitmap_words(row_count as usize) as jlong;
+        let (timestamps, out) =
+            unsafe { (slice(timestamps, row_count), slice_mut(bitmap_out, words)) };
+        select_interval(timestamps, ts_lo, ts_hi, out) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_selectIntervalSymbol(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    keys: *const i32,
+    key: jint,
+    row_count: jlong,
+    ts_lo: jlong,
+    ts_hi: jlong,
+    bitmap_out: *mut u64,
+) -> jlong {
+    catch_panic(|| {
+        let words = bitmap_words(row_count as usize) as jlong;
+        let (timestamps, keys, out) = unsafe {
+            (
+                slice(timestamps, row_count),
+                slice(keys, row_count),
+                slice_mut(bitmap_out, words),
+            )
+        };
+        select_interval_symbol(timestamps, keys, key, ts_lo, ts_hi, out) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_bitmapToRows(
+    _env: JNIEnv,
+    _class: JClass,
+    bitmap: *const u64,
+    row_count: jlong,
+    rows_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let words = bitmap_words(row_count as usize) as jlong;
+        let bitmap = unsafe { slice(bitmap, words) };
+        // Callers size the output for the worst case of every row being selected.
+        let out = unsafe { slice_mut(rows_out, row_count) };
+        bitmap_to_rows(bitmap, out) as jlong
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_type::{INT_NULL, LONG_NULL};
+
+    fn rows(bitmap: &[u64], row_count: usize) -> Vec<i64> {
+        let mut out = vec![0; row_count];
+        let n = bitmap_to_rows(bitmap, &mut out);
+        out.truncate(n);
+        out
+    }
+
+    #[test]
+    fn interval_bounds_are_inclusive() {
+        let ts = [LONG_NULL, 9, 10, 15, 20, 21];
+        let mut bitmap = [0u64; 1];
+        assert_eq!(select_interval(&ts, 10, 20, &mut bitmap), 3);
+        assert_eq!(rows(&bitmap, ts.len()), [2, 3, 4]);
+    }
+
+    #[test]
+    fn interval_spans_words() {
+        for row_count in [0, 1, 63, 64, 65, 128, 129] {
+            let ts: Vec<i64> = (0..row_count as i64).collect();
+            let mut bitmap = vec![u64::MAX; bitmap_words(row_count)];
+            let lo = row_count as i64 / 2;
+            let selected = select_interval(&ts, lo, i64::MAX, &mut bitmap);
+            assert_eq!(selected, row_count - lo as usize);
+            assert_eq!(
+                rows(&bitmap, row_count),
+                (lo..row_count as i64).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn interval_symbol_requires_key() {
+        let ts = [1, 2, 3, 4, 5];
+        let keys = [0, 1, INT_NULL, 1, 1];
+        let mut bitmap = [0u64; 1];
+        assert_eq!(select_interval_symbol(&ts, &keys, 1, 2, 4, &mut bitmap), 2);
+        assert_eq!(rows(&bitmap, ts.len()), [1, 3]);
+        assert_eq!(
+            select_interval_symbol(&ts, &keys, INT_NULL, 0, 10, &mut bitmap),
+            1
+        );
+        assert_eq!(rows(&bitmap, ts.len()), [2]);
+    }
+}
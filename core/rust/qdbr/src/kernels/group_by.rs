@@ -0,0 +1,265 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use super::aggregate::{DoubleAggregates, LongAggregates};
+use crate::column_type::SYMBOL_NULL;
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// One row of the key→aggregate table produced by the group-by-symbol kernels.
+/// `rows` counts all rows of the key, `aggregates.count` only the non-null values.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolAggregates<A> {
+    pub key: i64,
+    pub rows: i64,
+    pub aggregates: A,
+}
+
+pub type SymbolLongAggregates = SymbolAggregates<LongAggregates>;
+pub type SymbolDoubleAggregates = SymbolAggregates<DoubleAggregates>;
+
+/// Aggregates `values` per symbol key. Keys are dictionary indexes in `[0, symbol_count)`
+/// or `SYMBOL_NULL`. Entries are written to `out` for keys present in the buffer, null key
+/// first and then in key order; `out` must have room for `symbol_count + 1` entries.
+/// Returns the number of entries written, or `None` when a key is out of range.
+fn group_by_symbol<T: Copy, A: Copy>(
+    keys: &[i32],
+    values: &[T],
+    symbol_count: usize,
+    empty: A,
+    push: impl Fn(&mut A, T),
+    out: &mut [SymbolAggregates<A>],
+) -> Option<usize> {
+    // Slot 0 is reserved for the null key, so that slot = key + 1.
+    let mut table = vec![
+        SymbolAggregates {
+            key: 0,
+            rows: 0,
+            aggregates: empty,
+        };
+        symbol_count + 1
+    ];
+    for (&key, &value) in keys.iter().zip(values) {
+        let slot = if key == SYMBOL_NULL {
+            0
+        } else if key >= 0 && (key as usize) < symbol_count {
+            key as usize + 1
+        } else {
+            return None;
+        };
+        let entry = &mut table[slot];
+        entry.rows += 1;
+        push(&mut entry.aggregates, value);
+    }
+
+    let mut n = 0;
+    for (slot, entry) in table.iter().enumerate() {
+        if entry.rows > 0 {
+            out[n] = SymbolAggregates {
+                key: if slot == 0 {
+                    SYMBOL_NULL as i64
+                } else {
+                    slot as i64 - 1
+                },
+                ..*entry
+            };
+            n += 1;
+        }
+    }
+    Some(n)
+}
+
+pub fn group_by_symbol_long(
+    keys: &[i32],
+    values: &[i64],
+    symbol_count: usize,
+    out: &mut [SymbolLongAggregates],
+) -> Option<usize> {
+    group_by_symbol(
+        keys,
+        values,
+        symbol_count,
+        LongAggregates::EMPTY,
+        LongAggregates::push,
+        out,
+    )
+}
+
+pub fn group_by_symbol_double(
+    keys: &[i32],
+    values: &[f64],
+    symbol_count: usize,
+    out: &mut [SymbolDoubleAggregates],
+) -> Option<usize> {
+    group_by_symbol(
+        keys,
+        values,
+        symbol_count,
+        DoubleAggregates::EMPTY,
+        DoubleAggregates::push,
+        out,
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_groupBySymbolLong(
+    _env: JNIEnv,
+    _class: JClass,
+    keys: *const i32,
+    values: *const i64,
+    row_count: jlong,
+    symbol_count: jint,
+    table_out: *mut SymbolLongAggregates,
+) -> jlong {
+    catch_panic(|| {
+        if row_count < 0 || symbol_count < 0 {
+            return -1;
+        }
+        let (keys, values, out) = unsafe {
+            (
+                slice(keys, row_count),
+                slice(values, row_count),
+                slice_mut(table_out, symbol_count as jlong + 1),
+            )
+        };
+        group_by_symbol_long(keys, values, symbol_count as usize, out).map_or(-1, |n| n as jlong)
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_groupBySymbolDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    keys: *const i32,
+    values: *const f64,
+    row_count: jlong,
+    symbol_count: jint,
+    table_out: *mut SymbolDoubleAggregates,
+) -> jlong {
+    catch_panic(|| {
+        if row_count < 0 || symbol_count < 0 {
+            return -1;
+        }
+        let (keys, values, out) = unsafe {
+            (
+                slice(keys, row_count),
+                slice(values, row_count),
+                slice_mut(table_out, symbol_count as jlong + 1),
+            )
+        };
+        group_by_symbol_double(keys, values, symbol_count as usize, out).map_or(-1, |n| n as jlong)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_type::LONG_NULL;
+
+    #[test]
+    fn null_key_first_then_key_order() {
+        let keys = [2, SYMBOL_NULL, 0, 2, SYMBOL_NULL];
+        let values = [1, 2, 3, LONG_NULL, 5];
+        let mut out = [SymbolAggregates {
+            key: 0,
+            rows: 0,
+            aggregates: LongAggregates::EMPTY,
+        }; 4];
+        assert_eq!(group_by_symbol_long(&keys, &values, 3, &mut out), Some(3));
+        assert_eq!(
+            out[..3].iter().map(|e| (e.key, e.rows)).collect::<Vec<_>>(),
+            [(SYMBOL_NULL as i64, 2), (0, 1), (2, 2)]
+        );
+        let mut expected = LongAggregates::EMPTY;
+        expected.push(1);
+        expected.push(LONG_NULL);
+        assert_eq!(out[2].aggregates, expected);
+        assert_eq!(out[2].aggregates.count, 1);
+    }
+
+    #[test]
+    fn empty_and_out_of_range() {
+        let mut out = [SymbolAggregates {
+            key: 0,
+            rows: 0,
+            aggregates: DoubleAggregates::EMPTY,
+        }; 2];
+        assert_eq!(group_by_symbol_double(&[], &[], 1, &mut out), Some(0));
+        assert_eq!(group_by_symbol_double(&[1], &[1.0], 1, &mut out), None);
+        assert_eq!(group_by_symbol_double(&[-2], &[1.0], 1, &mut out), None);
+        assert_eq!(
+            group_by_symbol_double(&[0], &[f64::NAN], 1, &mut out),
+            Some(1)
+        );
+        assert_eq!((out[0].rows, out[0].aggregates.count), (1, 0));
+    }
+
+    #[test]
+    fn negative_counts() {
+        let (keys, values) = ([0], [1i64]);
+        let mut out = [SymbolAggregates {
+            key: 0,
+            rows: 0,
+            aggregates: LongAggregates::EMPTY,
+        }; 2];
+        for (row_count, symbol_count) in [(-1, 1), (1, -1), (jlong::MIN, 1), (1, jint::MIN)] {
+            let result = Java_io_questdb_std_Kernels_groupBySymbolLong(
+                crate::test_env(),
+                JClass::default(),
+                keys.as_ptr(),
+                values.as_ptr(),
+                row_count,
+                symbol_count,
+                out.as_mut_ptr(),
+            );
+            assert_eq!(result, -1);
+            let result = Java_io_questdb_std_Kernels_groupBySymbolDouble(
+                crate::test_env(),
+                JClass::default(),
+                keys.as_ptr(),
+                [1.0].as_ptr(),
+                row_count,
+                symbol_count,
+                out.as_mut_ptr().cast(),
+            );
+            assert_eq!(result, -1);
+        }
+        assert_eq!(out[0].rows, 0);
+        let result = Java_io_questdb_std_Kernels_groupBySymbolLong(
+            crate::test_env(),
+            JClass::default(),
+            keys.as_ptr(),
+            values.as_ptr(),
+            1,
+            1,
+            out.as_mut_ptr(),
+        );
+        assert_eq!((result, out[0].rows), (1, 1));
+    }
+}
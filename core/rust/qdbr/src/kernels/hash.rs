@@ -0,0 +1,245 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! 64-bit hashes of key columns for hash joins and GROUP BY.
+//!
+//! A single key column hashes the same as the corresponding `io.questdb.std.Hash` function:
+//! `hashInt64` for values up to 4 bytes, `hashLong64`, `hashLong128_64` and `hashLong256_64`
+//! for 8, 16 and 32 byte values and `hashMem64` over the bytes of STRING (UTF-16) and VARCHAR
+//! (UTF-8) values. Several key columns are combined polynomially, column by column. Nulls hash
+//! as their sentinel values, or as an empty value for STRING and VARCHAR.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use crate::column::{Column, ColumnDesc, ColumnError, Value};
+use crate::column_type as ct;
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// Multiplier of the Rust compiler's FxHasher, as in `Hash.M2`.
+const M2: u64 = 0x517c_c1b7_2722_0a95;
+
+fn fmix64(mut h: u64) -> u64 {
+    h = (h ^ (h >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h = (h ^ (h >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+pub fn hash_mem(bytes: &[u8]) -> u64 {
+    let mut h = 0u64;
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        h = h
+            .wrapping_mul(M2)
+            .wrapping_add(u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let mut tail = chunks.remainder();
+    if tail.len() >= 4 {
+        let v = i32::from_le_bytes(tail[..4].try_into().unwrap());
+        h = h.wrapping_mul(M2).wrapping_add(v as i64 as u64);
+        tail = &tail[4..];
+    }
+    for &b in tail {
+        h = h.wrapping_mul(M2).wrapping_add(b as i8 as i64 as u64);
+    }
+    fmix64(h)
+}
+
+fn word(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap())
+}
+
+fn hash_row(column: &Column, row: usize) -> Result<u64, ColumnError> {
+    if !column.is_fixed_size() {
+        return Ok(match column.value(row)? {
+            Value::Utf8(v) | Value::Utf16(v) => hash_mem(v),
+            _ => hash_mem(&[]),
+        });
+    }
+    let v = column.stored(row)?;
+    Ok(match v.len() {
+        1 | 2 => {
+            let signed = matches!(
+                column.tag(),
+                ct::BYTE | ct::SHORT | ct::GEOBYTE | ct::GEOSHORT
+            );
+            let k = match (v.len(), signed) {
+                (1, true) => v[0] as i8 as i32,
+                (1, false) => v[0] as i32,
+                (_, true) => i16::from_le_bytes([v[0], v[1]]) as i32,
+                (_, false) => u16::from_le_bytes([v[0], v[1]]) as i32,
+            };
+            fmix64(k as u32 as u64)
+        }
+        4 => fmix64(u32::from_le_bytes(v.try_into().unwrap()) as u64),
+        8 => fmix64(word(v, 0)),
+        16 => fmix64(word(v, 0).wrapping_mul(M2).wrapping_add(word(v, 1))),
+        _ => {
+            let h = (0..4).fold(0u64, |h, i| h.wrapping_mul(M2).wrapping_add(word(v, i)));
+            fmix64(h)
+        }
+    })
+}
+
+/// Writes the hash of the key columns of rows `[row_lo, row_hi)` to `out`.
+pub fn hash_rows(
+    keys: &[Column],
+    row_lo: usize,
+    row_hi: usize,
+    out: &mut [u64],
+) -> Result<(), ColumnError> {
+    out.fill(0);
+    for column in keys {
+        for (h, row) in out.iter_mut().zip(row_lo..row_hi) {
+            *h = h.wrapping_mul(M2).wrapping_add(hash_row(column, row)?);
+        }
+    }
+    Ok(())
+}
+
+/// `hashes_out` must have capacity for `row_hi - row_lo` longs. Returns 0, or a negative
+/// `ColumnError` code.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_hashRows(
+    _env: JNIEnv,
+    _class: JClass,
+    p_key_columns: *const ColumnDesc,
+    key_column_count: jint,
+    row_lo: jlong,
+    row_hi: jlong,
+    hashes_out: *mut u64,
+) -> jlong {
+    catch_panic(|| {
+        if key_column_count < 0 || row_lo < 0 || row_hi < row_lo {
+            return ColumnError::Corrupt.code();
+        }
+        let keys = unsafe { slice(p_key_columns, key_column_count as jlong) }
+            .iter()
+            .map(|desc| unsafe { Column::new(desc) })
+            .collect::<Result<Vec<_>, _>>();
+        let keys = match keys {
+            Ok(keys) if keys.iter().all(|k| k.row_count() >= row_hi as usize) => keys,
+            Ok(_) => return ColumnError::Corrupt.code(),
+            Err(e) => return e.code(),
+        };
+        let out = unsafe { slice_mut(hashes_out, row_hi - row_lo) };
+        match hash_rows(&keys, row_lo as usize, row_hi as usize, out) {
+            Ok(()) => 0,
+            Err(e) => e.code(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::{string_column, varchar_column};
+
+    // Expected values are the results of the corresponding `io.questdb.std.Hash` functions.
+    fn hashes(descs: &[ColumnDesc], row_lo: usize, row_hi: usize) -> Vec<i64> {
+        let keys: Vec<Column> = descs
+            .iter()
+            .map(|d| unsafe { Column::new(d) }.unwrap())
+            .collect();
+        let mut out = vec![0; row_hi - row_lo];
+        hash_rows(&keys, row_lo, row_hi, &mut out).unwrap();
+        out.into_iter().map(|h| h as i64).collect()
+    }
+
+    #[test]
+    fn mem_matches_java() {
+        assert_eq!(hash_mem(b"") as i64, 0);
+        assert_eq!(hash_mem(b"hello") as i64, -4693361336168523626);
+        assert_eq!(hash_mem(b"hello, world") as i64, -3054722160148891504);
+    }
+
+    #[test]
+    fn fixed_size_matches_java() {
+        let ints: Vec<u8> = [42, ct::INT_NULL]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_eq!(
+            hashes(&[ColumnDesc::of(ct::INT, &ints, &[])], 0, 2),
+            [-9148929187392628276, -2035967744440121210]
+        );
+        let longs: Vec<u8> = [42, ct::LONG_NULL]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_eq!(
+            hashes(&[ColumnDesc::of(ct::LONG, &longs, &[])], 0, 2),
+            [-9148929187392628276, -8108722261328812909]
+        );
+        let uuid: Vec<u8> = [1i64, 2].iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(
+            hashes(&[ColumnDesc::of(ct::UUID, &uuid, &[])], 0, 1),
+            [1865351971722957648]
+        );
+    }
+
+    #[test]
+    fn var_size_hash_bytes() {
+        let (data, aux) = varchar_column(&[Some("hello"), None, Some("hello, world")]);
+        let desc = ColumnDesc::of(ct::VARCHAR, &data, &aux);
+        assert_eq!(
+            hashes(&[desc], 0, 3),
+            [-4693361336168523626, 0, -3054722160148891504]
+        );
+        let (data, aux) = string_column(&[Some("hi"), None]);
+        let desc = ColumnDesc::of(ct::STRING, &data, &aux);
+        assert_eq!(
+            hashes(&[desc], 0, 2),
+            [hash_mem(&[b'h', 0, b'i', 0]) as i64, 0]
+        );
+    }
+
+    #[test]
+    fn combines_columns() {
+        let ints: Vec<u8> = [1i32, 2, 3].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let longs: Vec<u8> = [4i64, 5, 6].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let int = ColumnDesc::of(ct::INT, &ints, &[]);
+        let long = ColumnDesc::of(ct::LONG, &longs, &[]);
+        let a = hashes(&[int], 1, 3);
+        let b = hashes(&[long], 1, 3);
+        let ab = hashes(&[int, long], 1, 3);
+        for i in 0..2 {
+            assert_eq!(
+                ab[i],
+                (a[i] as u64).wrapping_mul(M2).wrapping_add(b[i] as u64) as i64
+            );
+        }
+        assert!(hashes(&[int, long], 3, 3).is_empty());
+    }
+
+    #[test]
+    fn rows_out_of_range() {
+        let ints = 7i32.to_le_bytes();
+        let keys = [unsafe { Column::new(&ColumnDesc::of(ct::INT, &ints, &[])) }.unwrap()];
+        let mut out = [0; 2];
+        assert_eq!(hash_rows(&keys, 0, 2, &mut out), Err(ColumnError::Corrupt));
+    }
+}
@@ -0,0 +1,596 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! LIKE, ILIKE and regular expression matching over STRING and VARCHAR columns.
+//!
+//! LIKE patterns follow `AbstractLikeStrFunctionFactory`: `%` matches any sequence, `_` a
+//! single character and `\` escapes the next character, the whole value has to match. Patterns
+//! that reduce to a prefix, suffix, substring or equality check skip the regex engine. Regular
+//! expressions match anywhere in the value, as `~` does. Nulls never match.
+//!
+//! Regular expressions use `java.util.regex` syntax. Where the `regex` crate reads the same
+//! syntax differently, the pattern is translated: `.` does not match line terminators, `$`
+//! also matches before a final line terminator and `\d`, `\w` and `\s` are ASCII classes.
+//! Patterns using anything else without an exact equivalent are not compiled, so the caller
+//! falls back to `java.util.regex`: lookaround, backreferences, possessive quantifiers, inline
+//! flags and groups other than `(?:`, `\b`, `\p{..}` classes, `\Q..\E` quotes, POSIX classes
+//! and `--`, `~~` inside brackets.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+use memchr::memmem;
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::column::{Column, ColumnDesc, ColumnError, Value};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+use crate::text::format;
+
+pub const KIND_LIKE: jint = 0;
+pub const KIND_ILIKE: jint = 1;
+pub const KIND_REGEX: jint = 2;
+
+enum Pattern {
+    /// `%`
+    Any,
+    Equals(Vec<u8>),
+    Prefix(Vec<u8>),
+    Suffix(Vec<u8>),
+    Contains(Box<memmem::Finder<'static>>),
+    Regex(Regex),
+}
+
+pub struct Matcher {
+    pattern: Pattern,
+    /// UTF-8 of STRING values.
+    scratch: Vec<u8>,
+}
+
+enum Token {
+    Literal(String),
+    One,
+    Many,
+}
+
+fn tokenize_like(pattern: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            '%' => {
+                // Consecutive wildcards match the same as one.
+                if !matches!(tokens.last(), Some(Token::Many)) {
+                    tokens.push(Token::Many);
+                }
+                continue;
+            }
+            '_' => {
+                tokens.push(Token::One);
+                continue;
+            }
+            '\\' => chars.next()?,
+            c => c,
+        };
+        match tokens.last_mut() {
+            Some(Token::Literal(s)) => s.push(literal),
+            _ => tokens.push(Token::Literal(literal.to_string())),
+        }
+    }
+    Some(tokens)
+}
+
+/// Case insensitive patterns fold ASCII letters only, as `Pattern.CASE_INSENSITIVE` does
+/// without `UNICODE_CASE`.
+fn like_regex(tokens: &[Token], case_insensitive: bool) -> Option<Regex> {
+    let mut re = String::from(r"\A(?:");
+    for token in tokens {
+        match token {
+            Token::Literal(s) if case_insensitive => {
+                for c in s.chars() {
+                    if c.is_ascii_alphabetic() {
+                        re.extend(['[', c.to_ascii_lowercase(), c.to_ascii_uppercase(), ']']);
+                    } else {
+                        re.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                    }
+                }
+            }
+            Token::Literal(s) => re.push_str(&regex::escape(s)),
+            Token::One => re.push('.'),
+            Token::Many => re.push_str(".*?"),
+        }
+    }
+    re.push_str(r")\z");
+    RegexBuilder::new(&re)
+        .dot_matches_new_line(true)
+        .build()
+        .ok()
+}
+
+/// Returns None for ILIKE patterns with non-ASCII characters: Java lower cases them with
+/// `String.toLowerCase()` before matching, which is locale dependent.
+fn like_pattern(pattern: &str, case_insensitive: bool) -> Option<Pattern> {
+    let tokens = tokenize_like(pattern)?;
+    if case_insensitive {
+        if !pattern.is_ascii() {
+            return None;
+        }
+        return like_regex(&tokens, true).map(Pattern::Regex);
+    }
+    let pattern = match tokens.as_slice() {
+        [Token::Many] => Pattern::Any,
+        [] => Pattern::Equals(Vec::new()),
+        [Token::Literal(s)] => Pattern::Equals(s.as_bytes().to_vec()),
+        [Token::Literal(s), Token::Many] => Pattern::Prefix(s.as_bytes().to_vec()),
+        [Token::Many, Token::Literal(s)] => Pattern::Suffix(s.as_bytes().to_vec()),
+        [Token::Many, Token::Literal(s), Token::Many] => {
+            Pattern::Contains(Box::new(memmem::Finder::new(s.as_bytes()).into_owned()))
+        }
+        _ => Pattern::Regex(like_regex(&tokens, false)?),
+    };
+    Some(pattern)
+}
+
+/// Line terminators of `java.util.regex` without the UNIX_LINES flag.
+const JAVA_DOT: &str = r"[^\n\r\x{85}\x{2028}\x{2029}]";
+const JAVA_DOLLAR: &str = r"(?:\r\n|[\n\r\x{85}\x{2028}\x{2029}])?\z";
+
+/// Translates an escape after a backslash, or returns None when the escape is not supported.
+fn java_escape(
+    c: char,
+    chars: &mut std::str::Chars,
+    in_class: bool,
+    re: &mut String,
+) -> Option<()> {
+    let class = |set: &str, negated: bool| match (in_class, negated) {
+        (true, false) => set.to_string(),
+        (_, true) => format!("[^{set}]"),
+        (false, false) => format!("[{set}]"),
+    };
+    match c {
+        'd' | 'D' => re.push_str(&class("0-9", c == 'D')),
+        'w' | 'W' => re.push_str(&class("0-9A-Za-z_", c == 'W')),
+        's' | 'S' => re.push_str(&class(r"\t\n\x0B\f\r ", c == 'S')),
+        'A' | 'z' if !in_class => {
+            re.push('\\');
+            re.push(c);
+        }
+        't' | 'n' | 'r' | 'f' | 'a' => {
+            re.push('\\');
+            re.push(c);
+        }
+        'x' => {
+            let mut hex = String::new();
+            let rest = chars.as_str();
+            if let Some(braced) = rest.strip_prefix('{') {
+                let end = braced.find('}')?;
+                hex.push_str(&braced[..end]);
+                chars.nth(end + 1);
+            } else {
+                hex.push_str(rest.get(..2)?);
+                chars.nth(1);
+            }
+            let code = u32::from_str_radix(&hex, 16).ok()?;
+            re.push_str(&format!("\\x{{{:X}}}", char::from_u32(code)? as u32));
+        }
+        'u' => {
+            let code = u32::from_str_radix(chars.as_str().get(..4)?, 16).ok()?;
+            chars.nth(3);
+            // Surrogates combine into one code point in Java, they are left to it.
+            re.push_str(&format!("\\x{{{:X}}}", char::from_u32(code)? as u32));
+        }
+        // Escaped punctuation is a literal in Java, some escapes are assertions in Rust.
+        c if c.is_ascii_punctuation() => re.push_str(&regex::escape(&c.to_string())),
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Translates a `java.util.regex` pattern to `regex` crate syntax with the same meaning, or
+/// returns None when the pattern uses syntax without an exact equivalent.
+fn java_regex(pattern: &str) -> Option<String> {
+    let mut re = String::with_capacity(pattern.len() + 16);
+    let mut chars = pattern.chars();
+    let mut class_depth = 0;
+    let mut quantified = false;
+    while let Some(c) = chars.next() {
+        let after_quantifier = std::mem::take(&mut quantified);
+        if class_depth > 0 {
+            match c {
+                '\\' => java_escape(chars.next()?, &mut chars, true, &mut re)?,
+                '[' => {
+                    // Java reads `[:alpha:]` as a set of characters, Rust as a POSIX class. A
+                    // leading `]` in a nested class is left to Java too.
+                    if chars.as_str().starts_with([':', ']']) || chars.as_str().starts_with("^]") {
+                        return None;
+                    }
+                    class_depth += 1;
+                    re.push(c);
+                }
+                ']' => {
+                    class_depth -= 1;
+                    re.push(c);
+                }
+                // Set difference and symmetric difference in Rust, literals in Java.
+                '-' | '~' if chars.as_str().starts_with(c) => return None,
+                c => re.push(c),
+            }
+            continue;
+        }
+        match c {
+            '\\' => java_escape(chars.next()?, &mut chars, false, &mut re)?,
+            '.' => re.push_str(JAVA_DOT),
+            '$' => re.push_str(JAVA_DOLLAR),
+            '(' => {
+                if let Some(group) = chars.as_str().strip_prefix('?') {
+                    if !group.starts_with(':') {
+                        return None;
+                    }
+                }
+                re.push(c);
+            }
+            '[' => {
+                class_depth = 1;
+                re.push(c);
+                if let Some(rest) = chars.as_str().strip_prefix('^') {
+                    re.push('^');
+                    chars = rest.chars();
+                }
+                // `]` first in a class is a literal in both.
+                if let Some(rest) = chars.as_str().strip_prefix(']') {
+                    re.push_str(r"\]");
+                    chars = rest.chars();
+                }
+            }
+            // Lazy quantifier. Possessive ones and repeated quantifiers are not supported.
+            '?' if after_quantifier => re.push(c),
+            '*' | '+' | '?' | '{' if after_quantifier => return None,
+            '*' | '+' | '?' => {
+                re.push(c);
+                quantified = true;
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest.find('}')?;
+                let (min, max) = rest[..end].split_once(',').unwrap_or((&rest[..end], "0"));
+                if min.is_empty() || !min.bytes().chain(max.bytes()).all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                re.push(c);
+                re.push_str(&rest[..=end]);
+                chars.nth(end);
+                quantified = true;
+            }
+            c => re.push(c),
+        }
+    }
+    Some(re)
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, kind: jint) -> Option<Self> {
+        let pattern = match kind {
+            KIND_LIKE => like_pattern(pattern, false)?,
+            KIND_ILIKE => like_pattern(pattern, true)?,
+            KIND_REGEX => Pattern::Regex(Regex::new(&java_regex(pattern)?).ok()?),
+            _ => return None,
+        };
+        Some(Matcher {
+            pattern,
+            scratch: Vec::new(),
+        })
+    }
+
+    fn is_match(&self, value: &[u8]) -> bool {
+        match &self.pattern {
+            Pattern::Any => true,
+            Pattern::Equals(s) => value == s.as_slice(),
+            Pattern::Prefix(s) => value.starts_with(s),
+            Pattern::Suffix(s) => value.ends_with(s),
+            Pattern::Contains(finder) => finder.find(value).is_some(),
+            Pattern::Regex(re) => re.is_match(value),
+        }
+    }
+
+    /// Writes indexes of matching rows in `[row_lo, row_hi)` to `out` and returns their count.
+    pub fn match_rows(
+        &mut self,
+        column: &Column,
+        row_lo: usize,
+        row_hi: usize,
+        out: &mut [i64],
+    ) -> Result<usize, ColumnError> {
+        let mut count = 0;
+        for row in row_lo..row_hi {
+            let matched = match column.value(row)? {
+                Value::Utf8(v) => self.is_match(v),
+                Value::Utf16(v) => {
+                    let mut scratch = std::mem::take(&mut self.scratch);
+                    scratch.clear();
+                    format::push_utf16(&mut scratch, v);
+                    let matched = self.is_match(&scratch);
+                    self.scratch = scratch;
+                    matched
+                }
+                Value::Null => false,
+                _ => return Err(ColumnError::UnsupportedType),
+            };
+            out[count] = row as i64;
+            count += matched as usize;
+        }
+        Ok(count)
+    }
+}
+
+/// Returns null when the pattern is not valid UTF-8, is malformed, is a regular expression
+/// without an exact translation, or the kind is unknown.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_createMatcher(
+    _env: JNIEnv,
+    _class: JClass,
+    pattern: *const u8,
+    pattern_size: jlong,
+    kind: jint,
+) -> *mut Matcher {
+    catch_panic(|| {
+        let pattern = unsafe { slice(pattern, pattern_size) };
+        match std::str::from_utf8(pattern)
+            .ok()
+            .and_then(|p| Matcher::new(p, kind))
+        {
+            Some(matcher) => Box::into_raw(Box::new(matcher)),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_destroyMatcher(
+    _env: JNIEnv,
+    _class: JClass,
+    matcher: *mut Matcher,
+) {
+    catch_panic(|| {
+        if !matcher.is_null() {
+            drop(unsafe { Box::from_raw(matcher) });
+        }
+    })
+}
+
+/// `rows_out` must have capacity for `row_hi - row_lo` rows. Returns the number of matching
+/// rows, or a negative `ColumnError` code, `Corrupt` also for a bad row range or a null
+/// matcher.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_matchRows(
+    _env: JNIEnv,
+    _class: JClass,
+    matcher: *mut Matcher,
+    p_column: *const ColumnDesc,
+    row_lo: jlong,
+    row_hi: jlong,
+    rows_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let column = match unsafe { Column::new(&*p_column) } {
+            Ok(column) => column,
+            Err(e) => return e.code(),
+        };
+        if matcher.is_null()
+            || row_lo < 0
+            || row_hi < row_lo
+            || column.row_count() < row_hi as usize
+        {
+            return ColumnError::Corrupt.code();
+        }
+        let matcher = unsafe { &mut *matcher };
+        let out = unsafe { slice_mut(rows_out, row_hi - row_lo) };
+        match matcher.match_rows(&column, row_lo as usize, row_hi as usize, out) {
+            Ok(count) => count as jlong,
+            Err(e) => e.code(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, kind: jint, value: &str) -> bool {
+        Matcher::new(pattern, kind)
+            .unwrap()
+            .is_match(value.as_bytes())
+    }
+
+    #[test]
+    fn like() {
+        assert!(matches("a%", KIND_LIKE, "abc"));
+        assert!(!matches("a%", KIND_LIKE, "bac"));
+        assert!(matches("%c", KIND_LIKE, "abc"));
+        assert!(matches("%b%", KIND_LIKE, "abc"));
+        assert!(matches("a_c", KIND_LIKE, "a\nc"));
+        assert!(matches("a\\%", KIND_LIKE, "a%"));
+        assert!(!matches("a\\%", KIND_LIKE, "ab"));
+        assert!(matches("A_C%", KIND_ILIKE, "abcd"));
+        assert!(Matcher::new("a\\", KIND_LIKE).is_none());
+        assert!(Matcher::new("a", 3).is_none());
+    }
+
+    #[test]
+    fn regex_translations() {
+        assert_eq!(
+            java_regex("a.b$").unwrap(),
+            format!("a{JAVA_DOT}b{JAVA_DOLLAR}")
+        );
+        assert_eq!(java_regex(r"[\d_]\W").unwrap(), "[0-9_][^0-9A-Za-z_]");
+        assert_eq!(
+            java_regex(r"\x41\x{1F600}\u00e9").unwrap(),
+            r"\x{41}\x{1F600}\x{E9}"
+        );
+        assert_eq!(java_regex(r"a{2,}?(?:b|c)").unwrap(), "a{2,}?(?:b|c)");
+    }
+
+    #[test]
+    fn regex_matches_as_java() {
+        // Matches anywhere, as Matcher.find().
+        assert!(matches("b+", KIND_REGEX, "abbc"));
+        // `.` does not match line terminators.
+        assert!(matches("a.b", KIND_REGEX, "a-b"));
+        assert!(!matches("a.b", KIND_REGEX, "a\nb"));
+        assert!(!matches("a.b", KIND_REGEX, "a\rb"));
+        assert!(!matches("a.b", KIND_REGEX, "a\u{2028}b"));
+        // `$` matches at the end and before a final line terminator.
+        assert!(matches("b$", KIND_REGEX, "ab"));
+        assert!(matches("b$", KIND_REGEX, "ab\n"));
+        assert!(matches("b$", KIND_REGEX, "ab\r\n"));
+        assert!(!matches("b$", KIND_REGEX, "ab\n\n"));
+        assert!(!matches("b$", KIND_REGEX, "abc"));
+        assert!(matches("^a", KIND_REGEX, "ab"));
+        assert!(!matches("^b", KIND_REGEX, "a\nb"));
+        // Classes are ASCII.
+        assert!(matches(r"^\d+$", KIND_REGEX, "42"));
+        assert!(!matches(r"\d", KIND_REGEX, "\u{663}"));
+        assert!(!matches(r"\w", KIND_REGEX, "é"));
+        assert!(matches(r"\W", KIND_REGEX, "é"));
+        assert!(matches(r"[\w.]+@", KIND_REGEX, "a.b@c"));
+        assert!(!matches(r"\s", KIND_REGEX, "\u{a0}"));
+        assert!(matches(r"\s", KIND_REGEX, "\u{b}"));
+        // Escaped punctuation is a literal.
+        assert!(matches(r"\<a\>", KIND_REGEX, "<a>"));
+        assert!(matches(r"[\[\]]", KIND_REGEX, "]"));
+        assert!(matches("^[]a.]+$", KIND_REGEX, "]a."));
+        assert!(!matches("[^]a]", KIND_REGEX, "]a"));
+        assert!(matches(r"[a-z&&[^b]]", KIND_REGEX, "c"));
+        assert!(!matches(r"^[a-z&&[^b]]$", KIND_REGEX, "b"));
+        assert!(matches("é+", KIND_REGEX, "ééé"));
+    }
+
+    #[test]
+    fn regex_falls_back_to_java() {
+        for pattern in [
+            // lookaround
+            "a(?=b)",
+            "a(?!b)",
+            "(?<=a)b",
+            "(?<!a)b",
+            // backreferences
+            r"(a)\1",
+            r"(?<x>a)\k<x>",
+            // possessive quantifiers, atomic groups
+            "a++",
+            "a*+b",
+            "a{2}+",
+            "(?>a)",
+            // inline flags and named groups
+            "(?i)a",
+            "(?m)^a",
+            "(?s:a.)",
+            "(?<name>a)",
+            // escapes without an exact equivalent
+            r"\bword\b",
+            r"\Bx",
+            r"\Z",
+            r"\G",
+            r"\p{Alpha}",
+            r"\P{L}",
+            r"\Qa.b\E",
+            r"\h",
+            r"\v",
+            r"\R",
+            r"\e",
+            r"\0101",
+            r"\cA",
+            r"\uD83D\uDE00",
+            // bracket syntax read differently
+            "[[:alpha:]]",
+            "[a--b]",
+            "[a~~b]",
+            "[a[]b]]",
+            // malformed
+            "(",
+            "[a",
+            "a{",
+            "a{,2}",
+            "a**",
+            "\\",
+        ] {
+            assert!(Matcher::new(pattern, KIND_REGEX).is_none(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn ilike_folds_ascii_only() {
+        assert!(matches("k_%", KIND_ILIKE, "Ka"));
+        assert!(matches("K.[a]%", KIND_ILIKE, "k.[A]b"));
+        assert!(!matches("K.[a]", KIND_ILIKE, "k_[A]"));
+        // Kelvin sign and long s fold to k and s in Unicode only.
+        assert!(!matches("k", KIND_ILIKE, "\u{212a}"));
+        assert!(!matches("%s%", KIND_ILIKE, "\u{17f}"));
+        assert!(matches("a_c", KIND_ILIKE, "A\u{c9}C"));
+        assert!(!matches("a_c", KIND_ILIKE, "A\u{c9}\u{c9}C"));
+        // Java lower cases non-ASCII pattern characters itself.
+        assert!(Matcher::new("%\u{e9}%", KIND_ILIKE).is_none());
+        assert!(Matcher::new("%\u{e9}%", KIND_LIKE).is_some());
+    }
+
+    #[test]
+    fn match_rows() {
+        use crate::column::varchar_column;
+        use crate::column_type::VARCHAR;
+
+        let (data, aux) = varchar_column(&[Some("ab"), None, Some("AB"), Some("cab")]);
+        let desc = ColumnDesc::of(VARCHAR, &data, &aux);
+        let match_rows = |matcher: *mut Matcher, row_lo: jlong, row_hi: jlong| {
+            let mut rows = vec![-1; 4];
+            let count = Java_io_questdb_std_Kernels_matchRows(
+                crate::test_env(),
+                JClass::default(),
+                matcher,
+                &desc,
+                row_lo,
+                row_hi,
+                rows.as_mut_ptr(),
+            );
+            rows.truncate(count.max(0) as usize);
+            (count, rows)
+        };
+        let pattern = "a%";
+        let matcher = Java_io_questdb_std_Kernels_createMatcher(
+            crate::test_env(),
+            JClass::default(),
+            pattern.as_ptr(),
+            pattern.len() as jlong,
+            KIND_ILIKE,
+        );
+        assert!(!matcher.is_null());
+        assert_eq!(match_rows(matcher, 0, 4), (2, vec![0, 2]));
+        assert_eq!(match_rows(matcher, 1, 3), (1, vec![2]));
+        assert_eq!(match_rows(matcher, 2, 5).0, ColumnError::Corrupt.code());
+        Java_io_questdb_std_Kernels_destroyMatcher(crate::test_env(), JClass::default(), matcher);
+        // createMatcher() returns null for patterns left to Java.
+        assert_eq!(
+            match_rows(std::ptr::null_mut(), 0, 4).0,
+            ColumnError::Corrupt.code()
+        );
+    }
+}
@@ -0,0 +1,319 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Out-of-order merge: a sorted timestamp column is merged with a sorted out-of-order index
+//! into a merge index, which then drives gathering of the payload columns.
+//!
+//! The merge index uses the `index_t` layout of the C++ O3 code, so it can be mixed with the
+//! existing `Vect` merge functions: each entry holds the timestamp and a row, with the top
+//! bit set when the row comes from the existing column rather than the out-of-order batch.
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+pub const COLUMN_ROW_FLAG: u64 = 1 << 63;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub ts: i64,
+    pub row: u64,
+}
+
+/// Merges `timestamps[lo..]` with `o3`, writing `timestamps.len() - lo + o3.len()` entries.
+/// On equal timestamps, rows of the existing column go first.
+pub fn merge_index(timestamps: &[i64], lo: usize, o3: &[IndexEntry], out: &mut [IndexEntry]) {
+    let (mut i, mut j) = (lo, 0);
+    let mut slots = out.iter_mut();
+    while i < timestamps.len() && j < o3.len() {
+        let slot = slots.next().unwrap();
+        let column = IndexEntry {
+            ts: timestamps[i],
+            row: i as u64 | COLUMN_ROW_FLAG,
+        };
+        let take_column = timestamps[i] <= o3[j].ts;
+        *slot = if take_column { column } else { o3[j] };
+        i += take_column as usize;
+        j += !take_column as usize;
+    }
+    for (i, slot) in (i..timestamps.len()).zip(slots.by_ref()) {
+        *slot = IndexEntry {
+            ts: timestamps[i],
+            row: i as u64 | COLUMN_ROW_FLAG,
+        };
+    }
+    for (slot, entry) in slots.zip(&o3[j..]) {
+        *slot = *entry;
+    }
+}
+
+/// Gathers values of the existing column (`column`) and the out-of-order batch (`o3`) in
+/// merge index order. Returns false when the index refers to a row past either source.
+pub fn merge_shuffle<T: Copy>(
+    column: &[T],
+    o3: &[T],
+    index: &[IndexEntry],
+    dest: &mut [T],
+) -> bool {
+    for (d, entry) in dest.iter_mut().zip(index) {
+        let row = (entry.row & !COLUMN_ROW_FLAG) as usize;
+        let src = if entry.row & COLUMN_ROW_FLAG != 0 {
+            column
+        } else {
+            o3
+        };
+        match src.get(row) {
+            Some(v) => *d = *v,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// `pIndexOut` must have capacity for `ts_count + o3_count` entries. Returns the number of
+/// entries written, or -1 on a negative count or a null buffer.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_mergeTimestampIndex(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    ts_lo: jlong,
+    ts_count: jlong,
+    o3_index: *const IndexEntry,
+    o3_count: jlong,
+    index_out: *mut IndexEntry,
+) -> jlong {
+    catch_panic(|| {
+        if ts_lo < 0 || ts_count < 0 || o3_count < 0 {
+            return -1;
+        }
+        let out_count = ts_count + o3_count;
+        if (ts_count > 0 && timestamps.is_null())
+            || (o3_count > 0 && o3_index.is_null())
+            || (out_count > 0 && index_out.is_null())
+        {
+            return -1;
+        }
+        let timestamps = unsafe { slice(timestamps, ts_lo + ts_count) };
+        let o3 = unsafe { slice(o3_index, o3_count) };
+        let out = unsafe { slice_mut(index_out, out_count) };
+        merge_index(timestamps, ts_lo as usize, o3, out);
+        out_count
+    })
+}
+
+/// Returns 0 on success, or -1 on a negative count, a null buffer, an unsupported value size
+/// or an index entry referring to a row out of range.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_mergeShuffle(
+    _env: JNIEnv,
+    _class: JClass,
+    column: *const u8,
+    column_row_count: jlong,
+    o3: *const u8,
+    o3_row_count: jlong,
+    index: *const IndexEntry,
+    index_count: jlong,
+    dest: *mut u8,
+    value_size: jint,
+) -> jlong {
+    catch_panic(|| {
+        unsafe fn shuffle<T: Copy>(
+            column: *const u8,
+            column_row_count: jlong,
+            o3: *const u8,
+            o3_row_count: jlong,
+            index: &[IndexEntry],
+            dest: *mut u8,
+        ) -> bool {
+            merge_shuffle(
+                slice(column as *const T, column_row_count),
+                slice(o3 as *const T, o3_row_count),
+                index,
+                slice_mut(dest as *mut T, index.len() as jlong),
+            )
+        }
+
+        if column_row_count < 0 || o3_row_count < 0 || index_count < 0 {
+            return -1;
+        }
+        if (column_row_count > 0 && column.is_null())
+            || (o3_row_count > 0 && o3.is_null())
+            || (index_count > 0 && (index.is_null() || dest.is_null()))
+        {
+            return -1;
+        }
+        let index = unsafe { slice(index, index_count) };
+        let ok = unsafe {
+            match value_size {
+                1 => shuffle::<u8>(column, column_row_count, o3, o3_row_count, index, dest),
+                2 => shuffle::<u16>(column, column_row_count, o3, o3_row_count, index, dest),
+                4 => shuffle::<u32>(column, column_row_count, o3, o3_row_count, index, dest),
+                8 => shuffle::<u64>(column, column_row_count, o3, o3_row_count, index, dest),
+                16 => shuffle::<[u64; 2]>(column, column_row_count, o3, o3_row_count, index, dest),
+                32 => shuffle::<[u64; 4]>(column, column_row_count, o3, o3_row_count, index, dest),
+                _ => false,
+            }
+        };
+        if ok {
+            0
+        } else {
+            -1
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(row: u64, ts: i64) -> IndexEntry {
+        IndexEntry {
+            ts,
+            row: row | COLUMN_ROW_FLAG,
+        }
+    }
+
+    fn o3(row: u64, ts: i64) -> IndexEntry {
+        IndexEntry { ts, row }
+    }
+
+    fn merge(timestamps: &[i64], lo: usize, o3: &[IndexEntry]) -> Vec<IndexEntry> {
+        let mut out = vec![IndexEntry { ts: 0, row: 0 }; timestamps.len() - lo + o3.len()];
+        merge_index(timestamps, lo, o3, &mut out);
+        out
+    }
+
+    // Same order as binary_merge_ts_long_index() of ooo.cpp: column rows win ties.
+    #[test]
+    fn ties_take_column_rows_first() {
+        let merged = merge(&[1, 2, 2, 3], 1, &[o3(0, 2), o3(1, 2), o3(2, 4)]);
+        assert_eq!(
+            merged,
+            [
+                column(1, 2),
+                column(2, 2),
+                o3(0, 2),
+                o3(1, 2),
+                column(3, 3),
+                o3(2, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_side_empty() {
+        assert_eq!(merge(&[5, 6], 0, &[]), [column(0, 5), column(1, 6)]);
+        assert_eq!(merge(&[5, 6], 2, &[o3(0, 1)]), [o3(0, 1)]);
+        assert!(merge(&[], 0, &[]).is_empty());
+    }
+
+    #[test]
+    fn shuffle_follows_index() {
+        let index = merge(&[10, 30], 0, &[o3(0, 20), o3(1, 40)]);
+        let mut dest = [0u32; 4];
+        assert!(merge_shuffle(&[100, 300], &[200, 400], &index, &mut dest));
+        assert_eq!(dest, [100, 200, 300, 400]);
+        assert!(!merge_shuffle(&[100], &[200, 400], &index, &mut dest));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let timestamps = [1i64, 2];
+        let entries = [o3(0, 1)];
+        let mut out = [o3(0, 0); 3];
+        let call = |ts_lo, ts_count, o3_index: *const IndexEntry, out: *mut IndexEntry| {
+            Java_io_questdb_std_Kernels_mergeTimestampIndex(
+                crate::test_env(),
+                JClass::default(),
+                timestamps.as_ptr(),
+                ts_lo,
+                ts_count,
+                o3_index,
+                1,
+                out,
+            )
+        };
+        assert_eq!(call(-1, 2, entries.as_ptr(), out.as_mut_ptr()), -1);
+        assert_eq!(call(0, -1, entries.as_ptr(), out.as_mut_ptr()), -1);
+        assert_eq!(call(0, 2, std::ptr::null(), out.as_mut_ptr()), -1);
+        assert_eq!(call(0, 2, entries.as_ptr(), std::ptr::null_mut()), -1);
+        assert_eq!(call(1, 1, entries.as_ptr(), out.as_mut_ptr()), 2);
+        assert_eq!(out[..2], [o3(0, 1), column(1, 2)]);
+    }
+
+    #[test]
+    fn shuffle_rejects_bad_arguments() {
+        let (values, o3_values) = ([100u64, 300], [200u64]);
+        let index = merge(&[10, 30], 0, &[o3(0, 20)]);
+        let mut dest = [0u64; 3];
+        let call = |column: *const u64,
+                    column_row_count,
+                    o3_values: *const u64,
+                    o3_row_count,
+                    index: *const IndexEntry,
+                    index_count,
+                    dest: *mut u64| {
+            Java_io_questdb_std_Kernels_mergeShuffle(
+                crate::test_env(),
+                JClass::default(),
+                column.cast(),
+                column_row_count,
+                o3_values.cast(),
+                o3_row_count,
+                index,
+                index_count,
+                dest.cast(),
+                8,
+            )
+        };
+        let (v, o, i, d) = (
+            values.as_ptr(),
+            o3_values.as_ptr(),
+            index.as_ptr(),
+            dest.as_mut_ptr(),
+        );
+        let null = std::ptr::null::<u64>();
+        assert_eq!(call(v, -1, o, 1, i, 3, d), -1);
+        assert_eq!(call(v, 2, o, -1, i, 3, d), -1);
+        assert_eq!(call(v, 2, o, 1, i, jlong::MIN, d), -1);
+        assert_eq!(call(null, 2, o, 1, i, 3, d), -1);
+        assert_eq!(call(v, 2, null, 1, i, 3, d), -1);
+        assert_eq!(call(v, 2, o, 1, std::ptr::null(), 3, d), -1);
+        assert_eq!(call(v, 2, o, 1, i, 3, std::ptr::null_mut()), -1);
+        assert_eq!(dest, [0; 3]);
+        // Null buffers are fine when empty.
+        assert_eq!(
+            call(null, 0, null, 0, std::ptr::null(), 0, std::ptr::null_mut()),
+            0
+        );
+        assert_eq!(call(v, 2, o, 1, i, 3, d), 0);
+        assert_eq!(dest, [100, 200, 300]);
+    }
+}
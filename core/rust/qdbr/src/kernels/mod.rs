@@ -0,0 +1,48 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Vectorised kernels over QuestDB column buffers in native memory.
+//!
+//! Buffers follow the in-memory column layout used by the storage engine, e.g. a
+//! timestamp column is a contiguous array of `i64` micros and a symbol column is a
+//! contiguous array of `i32` keys, with `Numbers` sentinels standing in for nulls.
+
+mod aggregate;
+mod compare;
+mod dedup;
+mod filter;
+mod group_by;
+mod hash;
+mod matcher;
+mod merge;
+mod sample_by;
+mod search;
+mod top_n;
+
+pub const WORD_BITS: usize = u64::BITS as usize;
+
+/// Number of `u64` words needed for a row-selection bitmap of `row_count` rows.
+pub fn bitmap_words(row_count: usize) -> usize {
+    row_count.div_ceil(WORD_BITS)
+}
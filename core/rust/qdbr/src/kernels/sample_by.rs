@@ -0,0 +1,400 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+use crate::text::timestamp::{
+    civil_from_days, days_from_civil, days_in_month, is_leap_year, MICROS_PER_DAY,
+};
+
+pub const UNIT_MICROS: jint = 0;
+pub const UNIT_MONTHS: jint = 1;
+pub const UNIT_YEARS: jint = 2;
+
+#[derive(Clone, Copy)]
+pub enum Stride {
+    Micros(i64),
+    Months(i64),
+    Years(i64),
+}
+
+impl Stride {
+    pub fn new(stride: i64, unit: jint) -> Option<Stride> {
+        match unit {
+            _ if stride <= 0 => None,
+            UNIT_MICROS => Some(Stride::Micros(stride)),
+            UNIT_MONTHS => Some(Stride::Months(stride)),
+            UNIT_YEARS => Some(Stride::Years(stride)),
+            _ => None,
+        }
+    }
+}
+
+/// Mirrors the Java `TimestampSampler` implementations: `round()` finds the start of the
+/// bucket of a timestamp and `next()` the start of the following bucket. Month and year
+/// buckets take the day of month and time of day of the origin, clamped to the end of
+/// shorter months, as `Timestamps.toMicros()` and `addMonth()` clamp. Month buckets are
+/// aligned to January and year buckets to years divisible by the stride, so the origin only
+/// contributes its month to year buckets.
+pub struct Sampler {
+    stride: Stride,
+    origin: i64,
+    month: u32,
+    day: u32,
+    time: i64,
+}
+
+fn date_micros(year: i64, month: u32, day: u32, time: i64) -> i64 {
+    let day = day.min(days_in_month(year, month));
+    days_from_civil(year, month, day) * MICROS_PER_DAY + time
+}
+
+/// `Timestamps.toMicros(year, leap, day, month, ...)`, which takes the month lengths and the
+/// start of `year` from `leap`, even when it is not the leap flag of `year`.
+fn java_date_micros(year: i64, leap: bool, month: u32, day: u32, time: i64) -> i64 {
+    // any year with the same month lengths
+    let like = if leap { 2000 } else { 2001 };
+    let year_start = days_from_civil(year, 1, 1)
+        + match year >= 0 && leap != is_leap_year(year) {
+            // yearMicros() only uses the flag for years from 0 on
+            true if leap => -1,
+            true => 1,
+            false => 0,
+        };
+    let days = days_from_civil(like, month, day.min(days_in_month(like, month)))
+        - days_from_civil(like, 1, 1);
+    (year_start + days) * MICROS_PER_DAY + time
+}
+
+fn year_month(ts: i64) -> (i64, u32) {
+    let (year, month, _) = civil_from_days(ts.div_euclid(MICROS_PER_DAY));
+    (year, month)
+}
+
+impl Sampler {
+    pub fn new(stride: Stride, origin: i64) -> Sampler {
+        let (_, month, day) = civil_from_days(origin.div_euclid(MICROS_PER_DAY));
+        Sampler {
+            stride,
+            origin,
+            month,
+            day,
+            time: origin.rem_euclid(MICROS_PER_DAY),
+        }
+    }
+
+    /// As `round()` of the Java samplers, the result is later than `ts` when `ts` is earlier
+    /// in its month (or year) than the origin.
+    pub fn round(&self, ts: i64) -> i64 {
+        match self.stride {
+            // Truncates towards zero, as the Java sampler does.
+            Stride::Micros(n) => self.origin + (ts - self.origin) / n * n,
+            Stride::Months(n) => {
+                let (year, month) = year_month(ts);
+                let month = ((month as i64 - 1) / n * n) as u32 + 1;
+                date_micros(year, month, self.day, self.time)
+            }
+            Stride::Years(n) => {
+                // The Java sampler uses the leap flag of `ts`'s year for the bucket year, so
+                // with a stride over 1 buckets of January and February origins can move by a
+                // day.
+                let (year, _) = year_month(ts);
+                let leap = is_leap_year(year);
+                java_date_micros(year - year % n, leap, self.month, self.day, self.time)
+            }
+        }
+    }
+
+    pub fn next(&self, bucket: i64) -> i64 {
+        match self.stride {
+            Stride::Micros(n) => bucket + n,
+            Stride::Months(n) => {
+                let (year, month) = year_month(bucket);
+                let total = year * 12 + month as i64 - 1 + n;
+                let month = total.rem_euclid(12) as u32 + 1;
+                date_micros(total.div_euclid(12), month, self.day, self.time)
+            }
+            Stride::Years(n) => {
+                let (year, _) = year_month(bucket);
+                date_micros(year + n, self.month, self.day, self.time)
+            }
+        }
+    }
+}
+
+/// Assigns the rows of an ascending timestamp buffer to buckets the way the Java SAMPLE BY
+/// cursors do: a row past the end of the current bucket starts the bucket `round(ts)`, which
+/// ends at `next(round(ts))`. Writes the index of its bucket for each row to `buckets` and the
+/// `[lo, hi)` timestamp bounds of each bucket to `bounds`, returns the bucket count. Empty
+/// buckets between rows are skipped.
+pub fn bucket_rows(
+    timestamps: &[i64],
+    sampler: &Sampler,
+    buckets: &mut [i64],
+    bounds: &mut [i64],
+) -> usize {
+    let mut count = 0;
+    let mut hi = i64::MIN;
+    for (&ts, bucket) in timestamps.iter().zip(buckets.iter_mut()) {
+        if count == 0 || ts >= hi {
+            let lo = sampler.round(ts);
+            hi = sampler.next(lo);
+            bounds[count * 2] = lo;
+            bounds[count * 2 + 1] = hi;
+            count += 1;
+        }
+        *bucket = count as i64 - 1;
+    }
+    count
+}
+
+/// `buckets_out` must have capacity for `row_count` longs and `bounds_out` for `2 * row_count`.
+/// Returns the bucket count, or -1 on unknown unit or non-positive stride.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_sampleByBuckets(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    origin: jlong,
+    stride: jlong,
+    unit: jint,
+    buckets_out: *mut i64,
+    bounds_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let Some(stride) = Stride::new(stride, unit) else {
+            return -1;
+        };
+        let timestamps = unsafe { slice(timestamps, row_count) };
+        let buckets = unsafe { slice_mut(buckets_out, row_count) };
+        let bounds = unsafe { slice_mut(bounds_out, row_count * 2) };
+        bucket_rows(timestamps, &Sampler::new(stride, origin), buckets, bounds) as jlong
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Expected bounds come from the Java samplers driven the way the SAMPLE BY cursors do.
+    fn sample(stride: Stride, origin: i64, timestamps: &[i64]) -> (Vec<i64>, Vec<i64>) {
+        let mut buckets = vec![0; timestamps.len()];
+        let mut bounds = vec![0; timestamps.len() * 2];
+        let count = bucket_rows(
+            timestamps,
+            &Sampler::new(stride, origin),
+            &mut buckets,
+            &mut bounds,
+        );
+        bounds.truncate(count * 2);
+        (buckets, bounds)
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(sample(Stride::Micros(1), 0, &[]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn micros_skip_empty_buckets() {
+        let (buckets, bounds) = sample(
+            Stride::Micros(3_600_000_000),
+            1704069000000000,
+            &[
+                1704069000000000,
+                1704072599999999,
+                1704072600000000,
+                1704085200000000,
+            ],
+        );
+        assert_eq!(buckets, [0, 0, 1, 2]);
+        assert_eq!(
+            bounds,
+            [
+                1704069000000000,
+                1704072600000000,
+                1704072600000000,
+                1704076200000000,
+                1704083400000000,
+                1704087000000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn months_clamp_to_month_end() {
+        // Origin 2024-01-31T12:00, rows up to 2024-03-31T12:00.
+        let (buckets, bounds) = sample(
+            Stride::Months(1),
+            1706702400000000,
+            &[
+                1706702400000000,
+                1709207999999999,
+                1709208000000000,
+                1711756800000000,
+                1711886400000000,
+            ],
+        );
+        assert_eq!(buckets, [0, 0, 1, 1, 2]);
+        assert_eq!(
+            bounds,
+            [
+                1706702400000000,
+                1709208000000000,
+                1709208000000000,
+                1711886400000000,
+                1711886400000000,
+                1714478400000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn months_align_to_january() {
+        // Origin 2024-05-10, the first bucket of a 3 month stride starts on 2024-04-10.
+        let (buckets, bounds) = sample(
+            Stride::Months(3),
+            1715299200000000,
+            &[
+                1715299200000000,
+                1720569599999999,
+                1720569600000000,
+                1735689600000000,
+            ],
+        );
+        assert_eq!(buckets, [0, 0, 1, 2]);
+        assert_eq!(
+            bounds,
+            [
+                1712707200000000,
+                1720569600000000,
+                1720569600000000,
+                1728518400000000,
+                1736467200000000,
+                1744243200000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn years_keep_origin_month_and_day() {
+        // Origin 2023-03-15, rows until 2026-07-01.
+        let (buckets, bounds) = sample(
+            Stride::Years(1),
+            1678838400000000,
+            &[
+                1678838400000000,
+                1710460799999999,
+                1710460800000000,
+                1782864000000000,
+            ],
+        );
+        assert_eq!(buckets, [0, 0, 1, 2]);
+        assert_eq!(
+            bounds,
+            [
+                1678838400000000,
+                1710460800000000,
+                1710460800000000,
+                1741996800000000,
+                1773532800000000,
+                1805068800000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn months_of_day_31_origin() {
+        // Origin 2023-01-31: a row of 2023-02-15 starts the bucket of 2023-02-28, rows from
+        // 2023-04-30 the bucket of 2023-04-30.
+        let (buckets, bounds) = sample(
+            Stride::Months(1),
+            1675123200000000,
+            &[1676419200000000, 1677542400000000, 1682812800000000],
+        );
+        assert_eq!(buckets, [0, 0, 1]);
+        assert_eq!(
+            bounds,
+            [
+                1677542400000000,
+                1680220800000000,
+                1682812800000000,
+                1685491200000000,
+            ]
+        );
+        // Origin 2023-12-31, 2 month stride: buckets of 2024-01-31 and 2024-03-31.
+        let (buckets, bounds) = sample(
+            Stride::Months(2),
+            1703980800000000,
+            &[1707523200000000, 1711756800000000, 1711843200000000],
+        );
+        assert_eq!(buckets, [0, 0, 1]);
+        assert_eq!(
+            bounds,
+            [
+                1706659200000000,
+                1711843200000000,
+                1711843200000000,
+                1717113600000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn years_take_leap_flag_of_row() {
+        // Origin 2023-01-15, 4 year stride: a row of 2021-06-01 rounds with the month lengths
+        // of 2021 to 2020-01-16, as the Java sampler does.
+        let (buckets, bounds) = sample(
+            Stride::Years(4),
+            1673740800000000,
+            &[1622505600000000, 1705276800000000],
+        );
+        assert_eq!(buckets, [0, 1]);
+        assert_eq!(
+            bounds,
+            [
+                1579132800000000,
+                1705276800000000,
+                1705276800000000,
+                1831507200000000,
+            ]
+        );
+        // Origin 2024-02-29, a row of 2025-06-01.
+        let (_, bounds) = sample(Stride::Years(4), 1709164800000000, &[1748736000000000]);
+        assert_eq!(bounds, [1709164800000000, 1835395200000000]);
+    }
+
+    #[test]
+    fn rejects_bad_stride() {
+        assert!(Stride::new(0, UNIT_MICROS).is_none());
+        assert!(Stride::new(-1, UNIT_MONTHS).is_none());
+        assert!(Stride::new(1, 3).is_none());
+        assert!(Stride::new(1, UNIT_YEARS).is_some());
+    }
+}
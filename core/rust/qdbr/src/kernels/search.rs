@@ -0,0 +1,149 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::objects::JClass;
+use jni::sys::jlong;
+use jni::JNIEnv;
+
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// Returns the `[lo, hi)` range of rows of an ascending timestamp buffer that fall into
+/// the inclusive interval `[ts_lo, ts_hi]`. The range is empty when nothing matches.
+pub fn interval_rows(timestamps: &[i64], ts_lo: i64, ts_hi: i64) -> (usize, usize) {
+    let lo = timestamps.partition_point(|&ts| ts < ts_lo);
+    let hi = lo + timestamps[lo..].partition_point(|&ts| ts <= ts_hi);
+    (lo, hi)
+}
+
+/// Writes for each probe the index of the latest row of an ascending timestamp buffer with a
+/// timestamp at or before the probe, or -1 when there is none. Ascending probes narrow the
+/// search to the rows after the previous match.
+pub fn asof_rows(timestamps: &[i64], probes: &[i64], out: &mut [i64]) {
+    let mut lo = 0;
+    let mut prev = i64::MIN;
+    for (&probe, row) in probes.iter().zip(out.iter_mut()) {
+        if probe < prev {
+            lo = 0;
+        }
+        prev = probe;
+        lo += timestamps[lo..].partition_point(|&ts| ts <= probe);
+        *row = lo as i64 - 1;
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_asofRows(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    probes: *const i64,
+    probe_count: jlong,
+    rows_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let timestamps = unsafe { slice(timestamps, row_count) };
+        let probes = unsafe { slice(probes, probe_count) };
+        let out = unsafe { slice_mut(rows_out, probe_count) };
+        asof_rows(timestamps, probes, out);
+        0
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_findRowsForInterval(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    ts_lo: jlong,
+    ts_hi: jlong,
+    range_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let timestamps = unsafe { slice(timestamps, row_count) };
+        let out = unsafe { slice_mut(range_out, 2) };
+        let (lo, hi) = interval_rows(timestamps, ts_lo, ts_hi);
+        out[0] = lo as i64;
+        out[1] = hi as i64;
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_is_inclusive() {
+        let ts = [10, 20, 20, 30, 40];
+        assert_eq!(interval_rows(&ts, 20, 30), (1, 4));
+        assert_eq!(interval_rows(&ts, 21, 29), (3, 3));
+        assert_eq!(interval_rows(&ts, i64::MIN, i64::MAX), (0, 5));
+        assert_eq!(interval_rows(&ts, 41, 50), (5, 5));
+        assert_eq!(interval_rows(&ts, 30, 20), (3, 3));
+        assert_eq!(interval_rows(&[], 0, 1), (0, 0));
+    }
+
+    #[test]
+    fn asof_takes_latest_row_at_or_before() {
+        let ts = [10, 20, 20, 30];
+        let mut out = [0; 6];
+        asof_rows(&ts, &[5, 10, 20, 25, 30, 100], &mut out);
+        assert_eq!(out, [-1, 0, 2, 2, 3, 3]);
+        // Probes that go back restart the search.
+        let mut out = [0; 3];
+        asof_rows(&ts, &[30, 10, 5], &mut out);
+        assert_eq!(out, [3, 0, -1]);
+        asof_rows(&[], &[1, 2, 3], &mut out);
+        assert_eq!(out, [-1, -1, -1]);
+    }
+
+    #[test]
+    fn exports_return_zero() {
+        let ts = [10i64, 20, 30];
+        let mut out = [0i64; 2];
+        let result = Java_io_questdb_std_Kernels_findRowsForInterval(
+            crate::test_env(),
+            JClass::default(),
+            ts.as_ptr(),
+            3,
+            15,
+            30,
+            out.as_mut_ptr(),
+        );
+        assert_eq!((result, out), (0, [1, 3]));
+        let result = Java_io_questdb_std_Kernels_asofRows(
+            crate::test_env(),
+            JClass::default(),
+            ts.as_ptr(),
+            3,
+            [5i64, 25].as_ptr(),
+            2,
+            out.as_mut_ptr(),
+        );
+        assert_eq!((result, out), (0, [-1, 1]));
+    }
+}
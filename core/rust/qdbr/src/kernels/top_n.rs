@@ -0,0 +1,150 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use jni::objects::JClass;
+use jni::sys::{jboolean, jlong};
+use jni::JNIEnv;
+
+use super::{bitmap_words, WORD_BITS};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+fn is_selected(bitmap: Option<&[u64]>, row: usize) -> bool {
+    bitmap.is_none_or(|b| b[row / WORD_BITS] & (1 << (row % WORD_BITS)) != 0)
+}
+
+/// Writes up to `n` row indexes with the latest (`last == true`) or the earliest timestamps
+/// to `out`, latest first or earliest first respectively. Ties are broken by row index, so
+/// for a designated timestamp the result matches `ORDER BY ts DESC/ASC LIMIT n`. When
+/// `bitmap` is given, only selected rows are considered. Returns the number of rows written.
+pub fn top_n_rows(
+    timestamps: &[i64],
+    bitmap: Option<&[u64]>,
+    n: usize,
+    last: bool,
+    out: &mut [i64],
+) -> usize {
+    if n == 0 {
+        return 0;
+    }
+    let candidates = timestamps
+        .iter()
+        .enumerate()
+        .filter(|&(row, _)| is_selected(bitmap, row))
+        .map(|(row, &ts)| (ts, row));
+
+    let rows: Vec<usize> = if last {
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for candidate in candidates {
+            heap.push(Reverse(candidate));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, row))| row)
+            .collect()
+    } else {
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for candidate in candidates {
+            heap.push(candidate);
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect()
+    };
+
+    for (dst, row) in out.iter_mut().zip(&rows) {
+        *dst = *row as i64;
+    }
+    rows.len()
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_std_Kernels_topNRows(
+    _env: JNIEnv,
+    _class: JClass,
+    timestamps: *const i64,
+    row_count: jlong,
+    bitmap: *const u64,
+    n: jlong,
+    last: jboolean,
+    rows_out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let timestamps = unsafe { slice(timestamps, row_count) };
+        let bitmap = if bitmap.is_null() {
+            None
+        } else {
+            Some(unsafe { slice(bitmap, bitmap_words(row_count as usize) as jlong) })
+        };
+        let n = n.min(row_count).max(0);
+        let out = unsafe { slice_mut(rows_out, n) };
+        top_n_rows(timestamps, bitmap, n as usize, last != 0, out) as jlong
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(timestamps: &[i64], bitmap: Option<&[u64]>, n: usize, last: bool) -> Vec<i64> {
+        let mut out = vec![0; n];
+        let count = top_n_rows(timestamps, bitmap, n, last, &mut out);
+        out.truncate(count);
+        out
+    }
+
+    #[test]
+    fn ties_break_by_row() {
+        let ts = [30, 10, 20, 30, 10];
+        assert_eq!(top(&ts, None, 3, true), [3, 0, 2]);
+        assert_eq!(top(&ts, None, 3, false), [1, 4, 2]);
+    }
+
+    #[test]
+    fn bounded_by_rows_and_selection() {
+        let ts = [1, 2, 3];
+        assert!(top(&ts, None, 0, true).is_empty());
+        assert!(top(&[], None, 2, true).is_empty());
+        assert_eq!(top(&ts, None, 5, false), [0, 1, 2]);
+        assert_eq!(top(&ts, Some(&[0b101]), 2, true), [2, 0]);
+        assert!(top(&ts, Some(&[0]), 2, false).is_empty());
+    }
+
+    #[test]
+    fn bitmap_spans_words() {
+        let ts: Vec<i64> = (0..65).collect();
+        let bitmap = [1, 1];
+        assert_eq!(top(&ts, Some(&bitmap), 2, true), [64, 0]);
+    }
+}
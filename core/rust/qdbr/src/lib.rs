@@ -1,4 +1,3 @@
-
 /*******************************************************************************
  *     ___                  _   ____  ____
  *    / _ \ _   _  ___  ___| |_|  _ \| __ )
@@ -25,6 +24,18 @@
 
 pub extern crate jni;
 
+mod allocator;
+mod column;
+mod column_type;
+mod compress;
+mod diagnostics;
+mod export;
+mod kernels;
+mod logging;
+mod mem;
+mod text;
+
+use diagnostics::catch_panic;
 use jni::sys::jlong;
 use jni::{objects::JClass, JNIEnv};
 
@@ -33,11 +44,21 @@ const _: fn() = || {
     let _ = core::mem::transmute::<jlong, *const i32>;
 };
 
+/// An env for calling JNI exports from tests. It is not backed by a JVM, so it only suits
+/// exports that ignore their `_env` argument.
+#[cfg(test)]
+pub(crate) fn test_env<'a>() -> JNIEnv<'a> {
+    unsafe { JNIEnv::from_raw(std::ptr::NonNull::dangling().as_ptr()) }.unwrap()
+}
+
 #[no_mangle]
 pub extern "system" fn Java_io_questdb_std_Os_initRust(_env: JNIEnv, _class: JClass) {
-    if std::env::var("RUST_BACKTRACE").is_err() {
-        std::env::set_var("RUST_BACKTRACE", "1");
-    }
+    catch_panic(|| {
+        if std::env::var("RUST_BACKTRACE").is_err() {
+            std::env::set_var("RUST_BACKTRACE", "1");
+        }
+        diagnostics::install_panic_hook();
+    })
 }
 
 #[no_mangle]
@@ -47,7 +68,7 @@ pub extern "system" fn Java_io_questdb_std_Os_rustSmokeTest(
     a: i64,
     b: i64,
 ) -> i64 {
-    a + b
+    catch_panic(|| a + b)
 }
 
 #[no_mangle]
@@ -55,5 +76,5 @@ pub extern "system" fn Java_io_questdb_std_Os_isRustReleaseBuild(
     _env: JNIEnv,
     _class: JClass,
 ) -> bool {
-    !cfg!(debug_assertions)
+    catch_panic(|| !cfg!(debug_assertions))
 }
@@ -0,0 +1,230 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Forwards records of the `log` facade to QuestDB's `Log`, through a static callback on the
+//! Java `RustLog` class.
+
+use std::cell::Cell;
+use std::sync::OnceLock;
+
+use jni::objects::{GlobalRef, JClass, JStaticMethodID, JString, JValue};
+use jni::signature::{Primitive, ReturnType};
+use jni::sys::jint;
+use jni::{JNIEnv, JavaVM};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::diagnostics::catch_panic;
+
+struct JavaLogger {
+    vm: JavaVM,
+    class: GlobalRef,
+    method: JStaticMethodID,
+}
+
+static LOGGER: OnceLock<JavaLogger> = OnceLock::new();
+
+thread_local! {
+    /// Set while a record is forwarded. The `jni` crate logs through the same facade, its
+    /// records raised by the forwarding itself would otherwise recurse.
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+impl Log for JavaLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        forward_once(record, log::max_level(), |record| self.forward(record));
+    }
+
+    fn flush(&self) {}
+}
+
+impl JavaLogger {
+    fn forward(&self, record: &Record) {
+        // Java threads calling into the library are attached already, this is a no-op for them.
+        let Ok(mut env) = self.vm.attach_current_thread_as_daemon() else {
+            return;
+        };
+        let _ = env.with_local_frame(4, |env| -> jni::errors::Result<()> {
+            let target = env.new_string(record.target())?;
+            let message = env.new_string(record.args().to_string())?;
+            let class: &JClass = self.class.as_obj().into();
+            let result = unsafe {
+                env.call_static_method_unchecked(
+                    class,
+                    self.method,
+                    ReturnType::Primitive(Primitive::Void),
+                    &[
+                        JValue::Int(record.level() as jint).as_jni(),
+                        JValue::Object(&target).as_jni(),
+                        JValue::Object(&message).as_jni(),
+                    ],
+                )
+            };
+            if result.is_err() && env.exception_check()? {
+                env.exception_clear()?;
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Passes `record` to `forward` when its level is enabled, unless the thread is forwarding a
+/// record already.
+fn forward_once(record: &Record, max_level: LevelFilter, forward: impl FnOnce(&Record)) {
+    if record.level() > max_level || FORWARDING.replace(true) {
+        return;
+    }
+    forward(record);
+    FORWARDING.set(false);
+}
+
+fn level_filter(level: jint) -> LevelFilter {
+    match level {
+        l if l <= 0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Registers the Java logger on the first call, later calls only change the level.
+/// Returns false when the logger could not be registered.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_log_RustLog_init0(
+    mut env: JNIEnv,
+    class: JClass,
+    max_level: jint,
+) -> bool {
+    catch_panic(|| {
+        if LOGGER.get().is_none() {
+            let logger = (|| -> jni::errors::Result<JavaLogger> {
+                Ok(JavaLogger {
+                    vm: env.get_java_vm()?,
+                    class: env.new_global_ref(&class)?,
+                    method: env.get_static_method_id(
+                        &class,
+                        "log",
+                        "(ILjava/lang/String;Ljava/lang/String;)V",
+                    )?,
+                })
+            })();
+            let Ok(logger) = logger else {
+                return false;
+            };
+            if LOGGER.set(logger).is_ok() && log::set_logger(LOGGER.get().unwrap()).is_err() {
+                return false;
+            }
+        }
+        log::set_max_level(level_filter(max_level));
+        true
+    })
+}
+
+/// Logs `message` at a `RustLog` level, for tests checking that records reach the Java log.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_log_RustLog_testLog(
+    mut env: JNIEnv,
+    _class: JClass,
+    level: jint,
+    message: JString,
+) {
+    catch_panic(|| {
+        let Ok(message) = env.get_string(&message) else {
+            return;
+        };
+        if let Some(level) = level_filter(level).to_level() {
+            log::log!(level, "{}", String::from(message));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn forwarded(level: Level, max_level: LevelFilter) -> bool {
+        let mut forwarded = false;
+        let args = format_args!("record");
+        let record = Record::builder().level(level).args(args).build();
+        forward_once(&record, max_level, |_| forwarded = true);
+        forwarded
+    }
+
+    #[test]
+    fn levels_are_filtered() {
+        // RustLog.LEVEL_OFF to LEVEL_TRACE, out of range levels are clamped.
+        let filters = [-1, 0, 1, 2, 3, 4, 5, 6].map(level_filter);
+        assert_eq!(
+            filters,
+            [
+                LevelFilter::Off,
+                LevelFilter::Off,
+                LevelFilter::Error,
+                LevelFilter::Warn,
+                LevelFilter::Info,
+                LevelFilter::Debug,
+                LevelFilter::Trace,
+                LevelFilter::Trace,
+            ]
+        );
+        assert!(forwarded(Level::Error, LevelFilter::Info));
+        assert!(forwarded(Level::Info, LevelFilter::Info));
+        assert!(!forwarded(Level::Debug, LevelFilter::Info));
+        assert!(!forwarded(Level::Error, LevelFilter::Off));
+    }
+
+    #[test]
+    fn forwarding_does_not_recurse() {
+        let args = format_args!("outer");
+        let record = Record::builder().level(Level::Info).args(args).build();
+        let mut calls = 0;
+        forward_once(&record, LevelFilter::Trace, |record| {
+            calls += 1;
+            // A record raised by the forwarding itself, as by the jni crate.
+            forward_once(record, LevelFilter::Trace, |_| panic!("recursed"));
+        });
+        assert_eq!(calls, 1);
+        // The guard is released once the record is forwarded.
+        forward_once(&record, LevelFilter::Trace, |_| calls += 1);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn logging_before_init0() {
+        // Tests never register the Java logger. Records are dropped without reaching a
+        // logger, so the panic hook reports to stderr instead.
+        assert!(LOGGER.get().is_none());
+        assert!(!log::log_enabled!(Level::Error));
+        log::error!("logged before init0");
+        log::set_max_level(LevelFilter::Trace);
+        log::info!("logged before init0");
+        assert!(!log::log_enabled!(Level::Error));
+    }
+}
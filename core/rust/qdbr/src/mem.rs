@@ -0,0 +1,49 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+use jni::sys::jlong;
+
+/// Borrows `len` values at `ptr`. A null pointer is allowed when `len` is zero.
+///
+/// # Safety
+/// `ptr` must point to `len` initialised values that outlive the returned slice.
+pub unsafe fn slice<'a, T>(ptr: *const T, len: jlong) -> &'a [T] {
+    debug_assert!(len >= 0);
+    if len == 0 {
+        return &[];
+    }
+    std::slice::from_raw_parts(ptr, len as usize)
+}
+
+/// Mutable counterpart of [`slice`].
+///
+/// # Safety
+/// `ptr` must point to `len` writable values that are not aliased elsewhere.
+pub unsafe fn slice_mut<'a, T>(ptr: *mut T, len: jlong) -> &'a mut [T] {
+    debug_assert!(len >= 0);
+    if len == 0 {
+        return &mut [];
+    }
+    std::slice::from_raw_parts_mut(ptr, len as usize)
+}
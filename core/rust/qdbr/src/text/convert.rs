@@ -0,0 +1,1208 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Batch conversions between text and column values.
+//!
+//! Text is held in the layout of [`super::csv`] VARCHAR columns: a data buffer and a 16-byte
+//! aux entry per value, `(offset, length)`, length being -1 for null. Parsers write the null
+//! sentinel for nulls and unparsable values and return the number of unparsable values.
+//! Formatters write straight into the output buffers and return the data size. When it exceeds
+//! the data capacity they stop writing at the first value that does not fit, leaving the data
+//! and aux entries before it behind, and the call has to be repeated with at least that
+//! capacity. Numbers take up to [`FORMAT_MAX_SIZE`] bytes per value.
+//!
+//! Decimals are longs scaled by `10^scale`, with a scale of 0 to [`MAX_DECIMAL_SCALE`] and
+//! `LONG_NULL` for null, so their range is `+-i64::MAX`. Conversions round half away from zero
+//! and return -1 on invalid scale.
+//!
+//! BINARY columns are encoded as base64 or hex text. Their aux holds a long data offset per
+//! value, the data a long length, -1 for null, followed by the bytes.
+
+use std::fmt::Write;
+
+use jni::objects::JClass;
+use jni::sys::{jint, jlong};
+use jni::JNIEnv;
+
+use super::{format, numbers};
+use crate::column_type::{INT_NULL, LONG_NULL};
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+/// Longest text of a formatted number, `-2.2250738585072014e-308` is 24 bytes.
+pub const FORMAT_MAX_SIZE: usize = 32;
+
+pub const MAX_DECIMAL_SCALE: jint = 18;
+
+pub type AuxEntry = [i64; 2];
+
+/// Returns the text of an entry, `Ok(None)` for null and `Err` when it is out of bounds.
+pub fn field(data: &[u8], entry: AuxEntry) -> Result<Option<&[u8]>, ()> {
+    let [offset, length] = entry;
+    if length == -1 {
+        return Ok(None);
+    }
+    let lo = usize::try_from(offset).map_err(|_| ())?;
+    let hi = lo
+        .checked_add(usize::try_from(length).map_err(|_| ())?)
+        .ok_or(())?;
+    data.get(lo..hi).map(Some).ok_or(())
+}
+
+pub fn parse_values<T: Copy>(
+    data: &[u8],
+    aux: &[AuxEntry],
+    out: &mut [T],
+    null: T,
+    parse: impl Fn(&[u8]) -> Option<T>,
+) -> usize {
+    let mut errors = 0;
+    for (&entry, value) in aux.iter().zip(out.iter_mut()) {
+        *value = match field(data, entry) {
+            Ok(None) => null,
+            Ok(Some(text)) => parse(text).unwrap_or_else(|| {
+                errors += 1;
+                null
+            }),
+            Err(()) => {
+                errors += 1;
+                null
+            }
+        };
+    }
+    errors
+}
+
+/// Writes the text of `count` values into the Java buffers. `push` appends the text of value
+/// `i` to a scratch vector and returns false for null, which must not write anything, or `Err`
+/// for an invalid value. From the first value that does not fit on, the values are only
+/// measured. Returns the data size.
+///
+/// # Safety
+/// `data_out` must be writable for `data_capacity` bytes and `aux_out` for `count` entries.
+pub unsafe fn write_text(
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+    mut push: impl FnMut(&mut Vec<u8>, usize) -> Result<bool, ()>,
+) -> Result<jlong, ()> {
+    let data_out = slice_mut(data_out, data_capacity.max(0));
+    let aux_out = slice_mut(aux_out, count);
+    let mut text = Vec::with_capacity(FORMAT_MAX_SIZE);
+    let mut size = 0;
+    let mut fits = true;
+    for (i, entry) in aux_out.iter_mut().enumerate() {
+        text.clear();
+        let not_null = push(&mut text, i)?;
+        let end = size + text.len();
+        fits = fits && end <= data_out.len();
+        if fits {
+            data_out[size..end].copy_from_slice(&text);
+            *entry = [size as i64, if not_null { text.len() as i64 } else { -1 }];
+        }
+        size = end;
+    }
+    Ok(size as jlong)
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+
+/// Standard base64 with padding, RFC 4648.
+pub fn push_base64(out: &mut Vec<u8>, bytes: &[u8]) {
+    let mut chunks = bytes.chunks_exact(3);
+    for chunk in chunks.by_ref() {
+        let n = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+        for shift in [18, 12, 6, 0] {
+            out.push(BASE64_CHARS[(n >> shift) as usize & 0x3F]);
+        }
+    }
+    match *chunks.remainder() {
+        [a] => {
+            let n = (a as u32) << 16;
+            out.extend_from_slice(&[
+                BASE64_CHARS[(n >> 18) as usize & 0x3F],
+                BASE64_CHARS[(n >> 12) as usize & 0x3F],
+                b'=',
+                b'=',
+            ]);
+        }
+        [a, b] => {
+            let n = (a as u32) << 16 | (b as u32) << 8;
+            out.extend_from_slice(&[
+                BASE64_CHARS[(n >> 18) as usize & 0x3F],
+                BASE64_CHARS[(n >> 12) as usize & 0x3F],
+                BASE64_CHARS[(n >> 6) as usize & 0x3F],
+                b'=',
+            ]);
+        }
+        _ => {}
+    }
+}
+
+/// Lowercase hex digits, two per byte.
+pub fn push_hex(out: &mut Vec<u8>, bytes: &[u8]) {
+    for &b in bytes {
+        out.push(HEX_CHARS[(b >> 4) as usize]);
+        out.push(HEX_CHARS[(b & 0xF) as usize]);
+    }
+}
+
+/// Returns the bytes of a BINARY value, `Ok(None)` for null and `Err` when it is out of bounds.
+fn binary_value(data: &[u8], offset: i64) -> Result<Option<&[u8]>, ()> {
+    let lo = usize::try_from(offset).map_err(|_| ())?;
+    let header = data.get(lo..lo.checked_add(8).ok_or(())?).ok_or(())?;
+    let length = i64::from_le_bytes(header.try_into().unwrap());
+    if length == -1 {
+        return Ok(None);
+    }
+    let length = usize::try_from(length).map_err(|_| ())?;
+    data.get(lo + 8..(lo + 8).checked_add(length).ok_or(())?)
+        .map(Some)
+        .ok_or(())
+}
+
+/// Encodes the values of a BINARY column as text with [`write_text`]. Returns the data size,
+/// which is larger than `data_capacity` when not all values were written, or -1 on out of
+/// bounds values.
+#[allow(clippy::too_many_arguments)]
+fn encode_binary_jni(
+    data: *const u8,
+    data_size: jlong,
+    aux: *const i64,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+    encode: fn(&mut Vec<u8>, &[u8]),
+) -> jlong {
+    let data = unsafe { slice(data, data_size) };
+    let offsets = unsafe { slice(aux, count) };
+    let size = unsafe {
+        write_text(count, data_out, data_capacity, aux_out, |out, i| {
+            let bytes = binary_value(data, offsets[i])?;
+            if let Some(bytes) = bytes {
+                encode(out, bytes);
+            }
+            Ok(bytes.is_some())
+        })
+    };
+    size.unwrap_or(-1)
+}
+
+/// Formats the values with [`write_text`]. Returns the data size, which is larger than
+/// `data_capacity` when not all values were written.
+pub fn format_jni<T: Copy>(
+    values: *const T,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+    push: impl Fn(&mut Vec<u8>, T) -> bool,
+) -> jlong {
+    let values = unsafe { slice(values, count) };
+    let size = unsafe {
+        write_text(count, data_out, data_capacity, aux_out, |out, i| {
+            Ok(push(out, values[i]))
+        })
+    };
+    size.unwrap_or(-1)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseInt(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    out: *mut i32,
+) -> jlong {
+    catch_panic(|| {
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, INT_NULL, numbers::parse_i32) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseLong(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, LONG_NULL, numbers::parse_i64) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseFloat(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    out: *mut f32,
+) -> jlong {
+    catch_panic(|| {
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, f32::NAN, numbers::parse_f32) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    out: *mut f64,
+) -> jlong {
+    catch_panic(|| {
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, f64::NAN, numbers::parse_f64) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatInt(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i32,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        format_jni(values, count, data_out, data_capacity, aux_out, |out, v| {
+            if v == INT_NULL {
+                return false;
+            }
+            format::push_i64(out, v as i64);
+            true
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatLong(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i64,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        format_jni(values, count, data_out, data_capacity, aux_out, |out, v| {
+            if v == LONG_NULL {
+                return false;
+            }
+            format::push_i64(out, v);
+            true
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatFloat(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const f32,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        format_jni(values, count, data_out, data_capacity, aux_out, |out, v| {
+            if !v.is_finite() {
+                return false;
+            }
+            format::push_f32(out, v);
+            true
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const f64,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        format_jni(values, count, data_out, data_capacity, aux_out, |out, v| {
+            if !v.is_finite() {
+                return false;
+            }
+            format::push_f64(out, v);
+            true
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_encodeBase64(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const i64,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        encode_binary_jni(
+            data,
+            data_size,
+            aux,
+            count,
+            data_out,
+            data_capacity,
+            aux_out,
+            push_base64,
+        )
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_encodeHex(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const i64,
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        encode_binary_jni(
+            data,
+            data_size,
+            aux,
+            count,
+            data_out,
+            data_capacity,
+            aux_out,
+            push_hex,
+        )
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseUuid(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    out: *mut [i64; 2],
+) -> jlong {
+    catch_panic(|| {
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, [LONG_NULL; 2], |text| {
+            numbers::parse_uuid(text).map(|(lo, hi)| [lo as i64, hi as i64])
+        }) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatUuid(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const [i64; 2],
+    count: jlong,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        format_jni(
+            values,
+            count,
+            data_out,
+            data_capacity,
+            aux_out,
+            |out, [lo, hi]| {
+                if lo == LONG_NULL && hi == LONG_NULL {
+                    return false;
+                }
+                format::push_uuid(out, lo as u64, hi as u64);
+                true
+            },
+        )
+    })
+}
+
+fn decimal_scale(scale: jint) -> Option<u32> {
+    (0..=MAX_DECIMAL_SCALE)
+        .contains(&scale)
+        .then_some(scale as u32)
+}
+
+/// Decimal of the shortest text of `v`, as `BigDecimal.valueOf()`, rounded half away from
+/// zero. 1.005 rounds to 1.01 at scale 2 although its binary value is slightly lower. Returns
+/// `None` on NaN, infinities and values that do not fit. `text` is scratch space.
+pub fn decimal_from_double(v: f64, scale: u32, text: &mut String) -> Option<i64> {
+    // Larger values overflow, smaller ones round to 0 at any scale and print long.
+    if v.is_nan() || v.abs() >= 1e19 {
+        return None;
+    }
+    if v.abs() < 1e-19 {
+        return Some(0);
+    }
+    text.clear();
+    write!(text, "{v}").unwrap();
+    numbers::parse_decimal(text.as_bytes(), scale)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseDecimal(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    scale: jint,
+    out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let Some(scale) = decimal_scale(scale) else {
+            return -1;
+        };
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count) };
+        parse_values(data, aux, out, LONG_NULL, |text| {
+            numbers::parse_decimal(text, scale)
+        }) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatDecimal(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i64,
+    count: jlong,
+    scale: jint,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        let Some(scale) = decimal_scale(scale) else {
+            return -1;
+        };
+        format_jni(values, count, data_out, data_capacity, aux_out, |out, v| {
+            if v == LONG_NULL {
+                return false;
+            }
+            format::push_decimal(out, v, scale);
+            true
+        })
+    })
+}
+
+/// Writes NaN for nulls. Returns 0, or -1 on invalid scale.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_decimalToDouble(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const i64,
+    count: jlong,
+    scale: jint,
+    out: *mut f64,
+) -> jlong {
+    catch_panic(|| {
+        let Some(scale) = decimal_scale(scale) else {
+            return -1;
+        };
+        let pow = 10f64.powi(scale as i32);
+        let values = unsafe { slice(values, count) };
+        let out = unsafe { slice_mut(out, count) };
+        for (&v, d) in values.iter().zip(out.iter_mut()) {
+            *d = if v == LONG_NULL {
+                f64::NAN
+            } else {
+                v as f64 / pow
+            };
+        }
+        0
+    })
+}
+
+/// Returns the number of values that do not fit, which are written as null along with NaNs,
+/// or -1 on invalid scale.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_doubleToDecimal(
+    _env: JNIEnv,
+    _class: JClass,
+    values: *const f64,
+    count: jlong,
+    scale: jint,
+    out: *mut i64,
+) -> jlong {
+    catch_panic(|| {
+        let Some(scale) = decimal_scale(scale) else {
+            return -1;
+        };
+        let values = unsafe { slice(values, count) };
+        let out = unsafe { slice_mut(out, count) };
+        let mut errors = 0;
+        let mut text = String::new();
+        for (&v, d) in values.iter().zip(out.iter_mut()) {
+            *d = match decimal_from_double(v, scale, &mut text) {
+                Some(d) => d,
+                None if v.is_nan() => LONG_NULL,
+                None => {
+                    errors += 1;
+                    LONG_NULL
+                }
+            };
+        }
+        errors
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type ParseFn<T> = extern "system" fn(
+        JNIEnv,
+        JClass,
+        *const u8,
+        jlong,
+        *const AuxEntry,
+        jlong,
+        *mut T,
+    ) -> jlong;
+    type FormatFn<T> =
+        extern "system" fn(JNIEnv, JClass, *const T, jlong, *mut u8, jlong, *mut AuxEntry) -> jlong;
+
+    fn text(values: &[Option<&str>]) -> (Vec<u8>, Vec<AuxEntry>) {
+        let mut data = Vec::new();
+        let aux = values
+            .iter()
+            .map(|value| {
+                let offset = data.len() as i64;
+                match value {
+                    Some(v) => {
+                        data.extend_from_slice(v.as_bytes());
+                        [offset, v.len() as i64]
+                    }
+                    None => [offset, -1],
+                }
+            })
+            .collect();
+        (data, aux)
+    }
+
+    fn texts(data: &[u8], aux: &[AuxEntry]) -> Vec<Option<String>> {
+        aux.iter()
+            .map(|&entry| {
+                field(data, entry)
+                    .unwrap()
+                    .map(|v| String::from_utf8(v.to_vec()).unwrap())
+            })
+            .collect()
+    }
+
+    fn parse<T: Copy + Default>(f: ParseFn<T>, values: &[Option<&str>]) -> (jlong, Vec<T>) {
+        let (data, aux) = text(values);
+        let mut out = vec![T::default(); values.len()];
+        let errors = f(
+            crate::test_env(),
+            JClass::default(),
+            data.as_ptr(),
+            data.len() as jlong,
+            aux.as_ptr(),
+            aux.len() as jlong,
+            out.as_mut_ptr(),
+        );
+        (errors, out)
+    }
+
+    /// Formats with a data buffer of `capacity` bytes, returns the result and the text when it
+    /// was written.
+    fn format<T>(
+        f: FormatFn<T>,
+        values: &[T],
+        capacity: usize,
+    ) -> (jlong, Option<Vec<Option<String>>>) {
+        let mut data = vec![0xAA; capacity + 1];
+        let mut aux = vec![[0; 2]; values.len()];
+        let size = f(
+            crate::test_env(),
+            JClass::default(),
+            values.as_ptr(),
+            values.len() as jlong,
+            data.as_mut_ptr(),
+            capacity as jlong,
+            aux.as_mut_ptr(),
+        );
+        // The byte past the capacity is never written.
+        assert_eq!(data[capacity], 0xAA);
+        if size > capacity as jlong {
+            return (size, None);
+        }
+        (size, Some(texts(&data, &aux)))
+    }
+
+    fn strings(values: &[Option<&str>]) -> Option<Vec<Option<String>>> {
+        Some(values.iter().map(|v| v.map(String::from)).collect())
+    }
+
+    #[test]
+    fn parse_numbers() {
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseInt;
+        assert_eq!(
+            parse(f, &[Some("42"), None, Some("-7"), Some("x"), Some("")]),
+            (2, vec![42, INT_NULL, -7, INT_NULL, INT_NULL])
+        );
+        assert_eq!(
+            parse(f, &[Some("2147483647"), Some("2147483648")]),
+            (1, vec![i32::MAX, INT_NULL])
+        );
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseLong;
+        assert_eq!(
+            parse(f, &[Some("-9223372036854775807"), None, Some("1.5")]),
+            (1, vec![-i64::MAX, LONG_NULL, LONG_NULL])
+        );
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseDouble;
+        let (errors, out) = parse(f, &[Some("1.5"), None, Some("-1e-3"), Some("abc")]);
+        assert_eq!(errors, 1);
+        assert_eq!(out[..1], [1.5]);
+        assert_eq!(out[2], -1e-3);
+        assert!(out[1].is_nan() && out[3].is_nan());
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseFloat;
+        let (errors, out) = parse(f, &[Some("0.25"), None]);
+        assert_eq!(errors, 0);
+        assert_eq!(out[0], 0.25);
+        assert!(out[1].is_nan());
+    }
+
+    #[test]
+    fn format_numbers() {
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatInt;
+        assert_eq!(
+            format(f, &[42, INT_NULL, i32::MIN + 1], 64),
+            (13, strings(&[Some("42"), None, Some("-2147483647")]))
+        );
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatLong;
+        assert_eq!(
+            format(f, &[LONG_NULL, 0], 64),
+            (1, strings(&[None, Some("0")]))
+        );
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatDouble;
+        assert_eq!(
+            format(f, &[1.5, f64::NAN, f64::INFINITY, -0.1], 64),
+            (7, strings(&[Some("1.5"), None, None, Some("-0.1")]))
+        );
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatFloat;
+        assert_eq!(
+            format(f, &[0.25, f32::NAN], 64),
+            (4, strings(&[Some("0.25"), None]))
+        );
+    }
+
+    #[test]
+    fn format_checks_capacity() {
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatLong;
+        let values = [i64::MAX, 1];
+        // 19 digits and 1.
+        assert_eq!(format(f, &values, 19), (20, None));
+        assert_eq!(format(f, &values, 0), (20, None));
+        assert_eq!(
+            format(f, &values, 20),
+            (20, strings(&[Some("9223372036854775807"), Some("1")]))
+        );
+        // Nulls need no capacity.
+        assert_eq!(format(f, &[LONG_NULL; 3], 0), (0, strings(&[None; 3])));
+        assert_eq!(format::<i64>(f, &[], 0), (0, Some(vec![])));
+
+        // Writing stops at the first value that does not fit.
+        let mut data = [0xAAu8; 4];
+        let mut aux = [[-2i64; 2]; 3];
+        let size = f(
+            crate::test_env(),
+            JClass::default(),
+            [12i64, 345, 6].as_ptr(),
+            3,
+            data.as_mut_ptr(),
+            4,
+            aux.as_mut_ptr(),
+        );
+        assert_eq!(size, 6);
+        assert_eq!(data, [b'1', b'2', 0xAA, 0xAA]);
+        assert_eq!(aux, [[0, 2], [-2, -2], [-2, -2]]);
+    }
+
+    /// `(data, aux)` of a BINARY column.
+    fn binary(values: &[Option<&[u8]>]) -> (Vec<u8>, Vec<i64>) {
+        let (mut data, mut aux) = (Vec::new(), Vec::new());
+        for value in values {
+            aux.push(data.len() as i64);
+            match value {
+                Some(v) => {
+                    data.extend_from_slice(&(v.len() as i64).to_le_bytes());
+                    data.extend_from_slice(v);
+                }
+                None => data.extend_from_slice(&(-1i64).to_le_bytes()),
+            }
+        }
+        (data, aux)
+    }
+
+    type EncodeFn = extern "system" fn(
+        JNIEnv,
+        JClass,
+        *const u8,
+        jlong,
+        *const i64,
+        jlong,
+        *mut u8,
+        jlong,
+        *mut AuxEntry,
+    ) -> jlong;
+
+    fn encode(
+        f: EncodeFn,
+        data: &[u8],
+        offsets: &[i64],
+        capacity: usize,
+    ) -> (jlong, Option<Vec<Option<String>>>) {
+        let mut out = vec![0xAA; capacity + 1];
+        let mut aux = vec![[0; 2]; offsets.len()];
+        let size = f(
+            crate::test_env(),
+            JClass::default(),
+            data.as_ptr(),
+            data.len() as jlong,
+            offsets.as_ptr(),
+            offsets.len() as jlong,
+            out.as_mut_ptr(),
+            capacity as jlong,
+            aux.as_mut_ptr(),
+        );
+        assert_eq!(out[capacity], 0xAA);
+        if size < 0 || size > capacity as jlong {
+            return (size, None);
+        }
+        (size, Some(texts(&out, &aux)))
+    }
+
+    #[test]
+    fn encode_binary() {
+        let base64 = Java_io_questdb_cutlass_text_TextConvertNative_encodeBase64;
+        let hex = Java_io_questdb_cutlass_text_TextConvertNative_encodeHex;
+        // RFC 4648 test vectors.
+        let values: Vec<Option<&[u8]>> = vec![
+            Some(b""),
+            Some(b"f"),
+            Some(b"fo"),
+            Some(b"foo"),
+            None,
+            Some(b"foobar"),
+        ];
+        let (data, offsets) = binary(&values);
+        assert_eq!(
+            encode(base64, &data, &offsets, 64),
+            (
+                20,
+                strings(&[
+                    Some(""),
+                    Some("Zg=="),
+                    Some("Zm8="),
+                    Some("Zm9v"),
+                    None,
+                    Some("Zm9vYmFy")
+                ])
+            )
+        );
+        let (data, offsets) = binary(&[Some(&[0x00, 0xAB, 0xFF]), None]);
+        assert_eq!(
+            encode(hex, &data, &offsets, 64),
+            (6, strings(&[Some("00abff"), None]))
+        );
+    }
+
+    #[test]
+    fn encode_binary_checks_bounds_and_capacity() {
+        let base64 = Java_io_questdb_cutlass_text_TextConvertNative_encodeBase64;
+        let hex = Java_io_questdb_cutlass_text_TextConvertNative_encodeHex;
+        let (data, offsets) = binary(&[Some(b"foobar"), Some(b"x")]);
+        // 8 + 4 bytes of base64, 12 + 2 hex digits.
+        assert_eq!(encode(base64, &data, &offsets, 11), (12, None));
+        assert_eq!(encode(hex, &data, &offsets, 13), (14, None));
+        assert_eq!(encode(hex, &data, &offsets, 14).0, 14);
+        // Offsets and lengths out of bounds.
+        assert_eq!(encode(hex, &data, &[data.len() as i64], 64), (-1, None));
+        assert_eq!(encode(hex, &data, &[-1], 64), (-1, None));
+        assert_eq!(encode(hex, &data[..10], &offsets[..1], 64), (-1, None));
+
+        // Writing stops at the first value that does not fit.
+        let mut out = [0u8; 10];
+        let mut aux = [[-2i64; 2]; 2];
+        let size = hex(
+            crate::test_env(),
+            JClass::default(),
+            data.as_ptr(),
+            data.len() as jlong,
+            [offsets[1], offsets[0]].as_ptr(),
+            2,
+            out.as_mut_ptr(),
+            out.len() as jlong,
+            aux.as_mut_ptr(),
+        );
+        assert_eq!(size, 14);
+        assert_eq!(&out[..3], b"78\0");
+        assert_eq!(aux, [[0, 2], [-2, -2]]);
+    }
+
+    #[test]
+    fn uuid_round_trip() {
+        // (lo, hi) and text from Numbers.appendUuid() and Uuid.parseLo()/parseHi().
+        let uuids: [([i64; 2], &str); 5] = [
+            ([1, 2], "00000000-0000-0002-0000-000000000001"),
+            ([-1, -1], "ffffffff-ffff-ffff-ffff-ffffffffffff"),
+            (
+                [0x0123456789abcdef, 0xfedcba9876543210u64 as i64],
+                "fedcba98-7654-3210-0123-456789abcdef",
+            ),
+            ([LONG_NULL, 0], "00000000-0000-0000-8000-000000000000"),
+            (
+                [-4941174770667877871, -6850330615249940744],
+                "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            ),
+        ];
+        let values: Vec<[i64; 2]> = uuids.iter().map(|(v, _)| *v).collect();
+        let texts: Vec<Option<&str>> = uuids.iter().map(|(_, t)| Some(*t)).collect();
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatUuid;
+        assert_eq!(format(f, &values, 5 * 36), (5 * 36, strings(&texts)));
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseUuid;
+        assert_eq!(parse(f, &texts), (0, values));
+        assert_eq!(
+            parse(f, &[Some("A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11")]),
+            (0, vec![[-4941174770667877871, -6850330615249940744]])
+        );
+    }
+
+    #[test]
+    fn uuid_nulls_and_malformed() {
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_formatUuid;
+        assert_eq!(
+            format(f, &[[LONG_NULL; 2], [1, 2]], 36),
+            (
+                36,
+                strings(&[None, Some("00000000-0000-0002-0000-000000000001")])
+            )
+        );
+        // A UUID does not fit FORMAT_MAX_SIZE.
+        assert_eq!(format(f, &[[1, 2]], FORMAT_MAX_SIZE), (36, None));
+
+        let f = Java_io_questdb_cutlass_text_TextConvertNative_parseUuid;
+        let malformed = [
+            "a0eebc999c0b4ef8bb6d6bb9bd380a11",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a111",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1g",
+            "a0eebc99-9c0b-4ef8-bb6d_6bb9bd380a11",
+            "+0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "",
+        ];
+        let values: Vec<Option<&str>> = malformed.iter().map(|t| Some(*t)).chain([None]).collect();
+        assert_eq!(
+            parse(f, &values),
+            (malformed.len() as jlong, vec![[LONG_NULL; 2]; values.len()])
+        );
+    }
+
+    fn parse_decimals(values: &[Option<&str>], scale: jint) -> (jlong, Vec<i64>) {
+        let (data, aux) = text(values);
+        let mut out = vec![0; values.len()];
+        let errors = Java_io_questdb_cutlass_text_TextConvertNative_parseDecimal(
+            crate::test_env(),
+            JClass::default(),
+            data.as_ptr(),
+            data.len() as jlong,
+            aux.as_ptr(),
+            aux.len() as jlong,
+            scale,
+            out.as_mut_ptr(),
+        );
+        (errors, out)
+    }
+
+    fn format_decimals(values: &[i64], scale: jint) -> (jlong, Vec<Option<String>>) {
+        let mut data = vec![0; values.len() * FORMAT_MAX_SIZE];
+        let mut aux = vec![[0; 2]; values.len()];
+        let size = Java_io_questdb_cutlass_text_TextConvertNative_formatDecimal(
+            crate::test_env(),
+            JClass::default(),
+            values.as_ptr(),
+            values.len() as jlong,
+            scale,
+            data.as_mut_ptr(),
+            data.len() as jlong,
+            aux.as_mut_ptr(),
+        );
+        if size < 0 {
+            return (size, Vec::new());
+        }
+        (size, texts(&data, &aux))
+    }
+
+    fn decimals_to_doubles(values: &[i64], scale: jint) -> (jlong, Vec<f64>) {
+        let mut out = vec![0.0; values.len()];
+        let result = Java_io_questdb_cutlass_text_TextConvertNative_decimalToDouble(
+            crate::test_env(),
+            JClass::default(),
+            values.as_ptr(),
+            values.len() as jlong,
+            scale,
+            out.as_mut_ptr(),
+        );
+        (result, out)
+    }
+
+    fn doubles_to_decimals(values: &[f64], scale: jint) -> (jlong, Vec<i64>) {
+        let mut out = vec![0; values.len()];
+        let errors = Java_io_questdb_cutlass_text_TextConvertNative_doubleToDecimal(
+            crate::test_env(),
+            JClass::default(),
+            values.as_ptr(),
+            values.len() as jlong,
+            scale,
+            out.as_mut_ptr(),
+        );
+        (errors, out)
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(
+            parse_decimals(
+                &[
+                    Some("1.005"),
+                    Some("-1.005"),
+                    Some("1.0049"),
+                    Some("12"),
+                    Some("+.5"),
+                    Some("-0"),
+                    None,
+                ],
+                2
+            ),
+            (0, vec![101, -101, 100, 1200, 50, 0, LONG_NULL])
+        );
+        assert_eq!(
+            parse_decimals(&[Some("2.5"), Some("-2.5"), Some("-.49")], 0),
+            (0, vec![3, -3, 0])
+        );
+        let malformed = [
+            Some(""),
+            Some("."),
+            Some("-"),
+            Some("1.2.3"),
+            Some("1e3"),
+            Some("1.5x"),
+        ];
+        assert_eq!(
+            parse_decimals(&malformed, 2),
+            (malformed.len() as jlong, vec![LONG_NULL; malformed.len()])
+        );
+        // i64::MIN is the null sentinel, so both bounds are +-i64::MAX.
+        assert_eq!(
+            parse_decimals(
+                &[
+                    Some("92233720368547758.07"),
+                    Some("-92233720368547758.07"),
+                    Some("92233720368547758.08"),
+                    Some("-92233720368547758.08"),
+                    Some("92233720368547758.075"),
+                    Some("99999999999999999999999"),
+                ],
+                2
+            ),
+            (
+                4,
+                vec![
+                    i64::MAX,
+                    -i64::MAX,
+                    LONG_NULL,
+                    LONG_NULL,
+                    LONG_NULL,
+                    LONG_NULL
+                ]
+            )
+        );
+        assert_eq!(
+            parse_decimals(
+                &[Some("9.2233720368547758074"), Some("0.0000000000000000005")],
+                18
+            ),
+            (0, vec![i64::MAX, 1])
+        );
+    }
+
+    #[test]
+    fn format_decimal() {
+        assert_eq!(
+            format_decimals(&[101, -101, -1, 0, LONG_NULL], 2),
+            (
+                18,
+                vec![
+                    Some("1.01".into()),
+                    Some("-1.01".into()),
+                    Some("-0.01".into()),
+                    Some("0.00".into()),
+                    None
+                ]
+            )
+        );
+        assert_eq!(
+            format_decimals(&[i64::MAX, -i64::MAX, 7], 0),
+            (
+                40,
+                vec![
+                    Some("9223372036854775807".into()),
+                    Some("-9223372036854775807".into()),
+                    Some("7".into())
+                ]
+            )
+        );
+        assert_eq!(
+            format_decimals(&[i64::MAX, -1], 18),
+            (
+                41,
+                vec![
+                    Some("9.223372036854775807".into()),
+                    Some("-0.000000000000000001".into())
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn decimal_doubles() {
+        let (result, out) = decimals_to_doubles(&[101, -101, 0, LONG_NULL], 2);
+        assert_eq!(result, 0);
+        assert_eq!(out[..3], [1.01, -1.01, 0.0]);
+        assert!(out[3].is_nan());
+        assert_eq!(
+            decimals_to_doubles(&[-1, i64::MAX], 18),
+            (0, vec![-1e-18, 9.223372036854776])
+        );
+
+        assert_eq!(
+            doubles_to_decimals(&[1.005, -1.005, 0.145, 2.675, 1.0049, f64::NAN, -0.0], 2),
+            (0, vec![101, -101, 15, 268, 100, LONG_NULL, 0])
+        );
+        assert_eq!(
+            doubles_to_decimals(&[2.5, -2.5, 0.49999999999999994, 1e-300], 0),
+            (0, vec![3, -3, 0, 0])
+        );
+        assert_eq!(
+            doubles_to_decimals(&[5e-19, -5e-19, 4.9e-19, 1.5], 18),
+            (0, vec![1, -1, 0, 1_500_000_000_000_000_000])
+        );
+        // Out of range values are nulls and counted, NaN is not.
+        assert_eq!(
+            doubles_to_decimals(
+                &[
+                    9.2e18,
+                    -9.2e18,
+                    9.3e18,
+                    i64::MIN as f64,
+                    1e300,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY
+                ],
+                0
+            ),
+            (
+                5,
+                vec![
+                    9_200_000_000_000_000_000,
+                    -9_200_000_000_000_000_000,
+                    LONG_NULL,
+                    LONG_NULL,
+                    LONG_NULL,
+                    LONG_NULL,
+                    LONG_NULL
+                ]
+            )
+        );
+        assert_eq!(doubles_to_decimals(&[10.0], 18), (1, vec![LONG_NULL]));
+    }
+
+    #[test]
+    fn decimal_scale_is_checked() {
+        for scale in [-1, MAX_DECIMAL_SCALE + 1, jint::MIN, jint::MAX] {
+            assert_eq!(parse_decimals(&[Some("1")], scale), (-1, vec![0]));
+            assert_eq!(format_decimals(&[1], scale), (-1, Vec::new()));
+            assert_eq!(decimals_to_doubles(&[1], scale), (-1, vec![0.0]));
+            assert_eq!(doubles_to_decimals(&[1.0], scale), (-1, vec![0]));
+        }
+        assert_eq!(
+            parse_decimals(&[Some("1")], MAX_DECIMAL_SCALE),
+            (0, vec![10i64.pow(18)])
+        );
+    }
+}
@@ -0,0 +1,807 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Native CSV parser for `COPY`. The parser is fed chunks of a file and appends complete
+//! rows to per-column native buffers, carrying incomplete trailing rows over to the next
+//! chunk. Java reads the buffers after each batch and then clears them.
+//!
+//! Buffer layouts: fixed-size types use their storage layout with `Numbers` null
+//! sentinels, BOOLEAN/BYTE/SHORT store 0 for empty fields. VARCHAR and SYMBOL store UTF-8
+//! bytes in the data buffer and a 16-byte aux entry per row, `(offset, length)`, length being
+//! -1 for null; SYMBOL values are left for Java to look up in the symbol table. STRING uses
+//! its storage layout: an int length (-1 for null) and UTF-16 chars in the data buffer, and
+//! the long data offset of each row in the aux buffer. Empty unquoted fields are nulls, `""`
+//! is an empty string.
+
+use jni::objects::JClass;
+use jni::sys::{jboolean, jbyte, jint, jlong};
+use jni::JNIEnv;
+
+use super::numbers::{parse_bool, parse_f32, parse_f64, parse_i16, parse_i32, parse_i64, parse_i8};
+use super::scan::{find_any, find_byte};
+use super::timestamp::parse_timestamp;
+use crate::column_type::{self, INT_NULL, LONG_NULL};
+use crate::diagnostics::catch_panic;
+use crate::mem::slice;
+
+enum Values {
+    Skip,
+    Boolean(Vec<u8>),
+    Byte(Vec<i8>),
+    Short(Vec<i16>),
+    Int(Vec<i32>),
+    Long(Vec<i64>),
+    Date(Vec<i64>),
+    Timestamp(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Varchar { aux: Vec<[i64; 2]>, data: Vec<u8> },
+    String { aux: Vec<i64>, data: Vec<u8> },
+}
+
+pub struct Column {
+    values: Values,
+    errors: i64,
+}
+
+/// Appends the parsed value, or `null` for empty fields and values that fail to parse.
+/// Returns false when the value failed to parse.
+fn push_parsed<T>(
+    values: &mut Vec<T>,
+    field: &[u8],
+    null: T,
+    parse: impl Fn(&[u8]) -> Option<T>,
+) -> bool {
+    if field.is_empty() {
+        values.push(null);
+        return true;
+    }
+    match parse(field) {
+        Some(v) => {
+            values.push(v);
+            true
+        }
+        None => {
+            values.push(null);
+            false
+        }
+    }
+}
+
+impl Column {
+    fn new(column_type: i32) -> Option<Self> {
+        let values = match column_type::tag(column_type) {
+            column_type::UNDEFINED => Values::Skip,
+            column_type::BOOLEAN => Values::Boolean(vec![]),
+            column_type::BYTE => Values::Byte(vec![]),
+            column_type::SHORT => Values::Short(vec![]),
+            column_type::INT => Values::Int(vec![]),
+            column_type::LONG => Values::Long(vec![]),
+            column_type::DATE => Values::Date(vec![]),
+            column_type::TIMESTAMP => Values::Timestamp(vec![]),
+            column_type::FLOAT => Values::Float(vec![]),
+            column_type::DOUBLE => Values::Double(vec![]),
+            column_type::VARCHAR | column_type::SYMBOL => Values::Varchar {
+                aux: vec![],
+                data: vec![],
+            },
+            column_type::STRING => Values::String {
+                aux: vec![],
+                data: vec![],
+            },
+            _ => return None,
+        };
+        Some(Column { values, errors: 0 })
+    }
+
+    fn push(&mut self, field: &[u8], quoted: bool) -> bool {
+        match &mut self.values {
+            Values::Skip => true,
+            Values::Boolean(v) => push_parsed(v, field, 0, |f| parse_bool(f).map(u8::from)),
+            Values::Byte(v) => push_parsed(v, field, 0, parse_i8),
+            Values::Short(v) => push_parsed(v, field, 0, parse_i16),
+            Values::Int(v) => push_parsed(v, field, INT_NULL, parse_i32),
+            Values::Long(v) => push_parsed(v, field, LONG_NULL, parse_i64),
+            Values::Date(v) => push_parsed(v, field, LONG_NULL, |f| {
+                parse_timestamp(f).map(|micros| micros.div_euclid(1000))
+            }),
+            Values::Timestamp(v) => push_parsed(v, field, LONG_NULL, parse_timestamp),
+            Values::Float(v) => push_parsed(v, field, f32::NAN, parse_f32),
+            Values::Double(v) => push_parsed(v, field, f64::NAN, parse_f64),
+            Values::Varchar { aux, data } => {
+                let offset = data.len() as i64;
+                if field.is_empty() && !quoted {
+                    aux.push([offset, -1]);
+                    true
+                } else if std::str::from_utf8(field).is_ok() {
+                    aux.push([offset, field.len() as i64]);
+                    data.extend_from_slice(field);
+                    true
+                } else {
+                    aux.push([offset, -1]);
+                    false
+                }
+            }
+            Values::String { aux, data } => {
+                let offset = data.len();
+                aux.push(offset as i64);
+                if field.is_empty() && !quoted {
+                    data.extend_from_slice(&(-1i32).to_le_bytes());
+                    true
+                } else if let Ok(text) = std::str::from_utf8(field) {
+                    data.extend_from_slice(&[0; 4]);
+                    text.encode_utf16()
+                        .for_each(|c| data.extend_from_slice(&c.to_le_bytes()));
+                    let len = (data.len() - offset - 4) as i32 / 2;
+                    data[offset..offset + 4].copy_from_slice(&len.to_le_bytes());
+                    true
+                } else {
+                    data.extend_from_slice(&(-1i32).to_le_bytes());
+                    false
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        match &mut self.values {
+            Values::Skip => {}
+            Values::Boolean(v) => v.clear(),
+            Values::Byte(v) => v.clear(),
+            Values::Short(v) => v.clear(),
+            Values::Int(v) => v.clear(),
+            Values::Long(v) | Values::Date(v) | Values::Timestamp(v) => v.clear(),
+            Values::Float(v) => v.clear(),
+            Values::Double(v) => v.clear(),
+            Values::Varchar { aux, data } => {
+                aux.clear();
+                data.clear();
+            }
+            Values::String { aux, data } => {
+                aux.clear();
+                data.clear();
+            }
+        }
+        self.errors = 0;
+    }
+
+    /// Address and size in bytes of the data buffer.
+    fn data(&self) -> (*const u8, usize) {
+        fn raw<T>(v: &[T]) -> (*const u8, usize) {
+            (v.as_ptr() as *const u8, std::mem::size_of_val(v))
+        }
+        match &self.values {
+            Values::Skip => (std::ptr::null(), 0),
+            Values::Boolean(v) => raw(v),
+            Values::Byte(v) => raw(v),
+            Values::Short(v) => raw(v),
+            Values::Int(v) => raw(v),
+            Values::Long(v) | Values::Date(v) | Values::Timestamp(v) => raw(v),
+            Values::Float(v) => raw(v),
+            Values::Double(v) => raw(v),
+            Values::Varchar { data, .. } | Values::String { data, .. } => raw(data),
+        }
+    }
+
+    fn aux(&self) -> *const u8 {
+        match &self.values {
+            Values::Varchar { aux, .. } => aux.as_ptr() as *const u8,
+            Values::String { aux, .. } => aux.as_ptr() as *const u8,
+            _ => std::ptr::null(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Field {
+    start: usize,
+    end: usize,
+    quoted: bool,
+    // Quoted field containing doubled quotes, which have to be collapsed.
+    escaped: bool,
+}
+
+fn unescape(field: &[u8], quote: u8, out: &mut Vec<u8>) {
+    out.clear();
+    let mut prev_quote = false;
+    for &b in field {
+        if b == quote && prev_quote {
+            prev_quote = false;
+            continue;
+        }
+        prev_quote = b == quote;
+        out.push(b);
+    }
+}
+
+/// Tokenizer state of a row that ran out of input. The fields before `field` are kept in
+/// `CsvParser::fields`.
+#[derive(Clone, Copy)]
+struct Partial {
+    // Field being tokenized, or `None` when the next field starts at `scan_from`.
+    field: Option<Field>,
+    // Where to continue looking for the closing quote when `in_quotes`, otherwise for the
+    // end of the field.
+    scan_from: usize,
+    in_quotes: bool,
+    malformed: bool,
+}
+
+pub struct CsvParser {
+    delimiter: u8,
+    quote: u8,
+    skip_header: bool,
+    columns: Vec<Column>,
+    row_count: usize,
+    // One byte per row, set when any field of the row failed to parse or the row had
+    // the wrong number of fields.
+    row_errors: Vec<u8>,
+    // Incomplete row left over from the previous chunk.
+    carry: Vec<u8>,
+    // Where tokenizing of the incomplete row stopped, positions are relative to its start.
+    partial: Option<Partial>,
+    fields: Vec<Field>,
+    unescaped: Vec<u8>,
+}
+
+impl CsvParser {
+    /// Returns `None` when any of the column types is not supported.
+    pub fn new(delimiter: u8, quote: u8, skip_header: bool, column_types: &[i32]) -> Option<Self> {
+        let columns = column_types
+            .iter()
+            .map(|&t| Column::new(t))
+            .collect::<Option<Vec<_>>>()?;
+        Some(CsvParser {
+            delimiter,
+            quote,
+            skip_header,
+            columns,
+            row_count: 0,
+            row_errors: vec![],
+            carry: vec![],
+            partial: None,
+            fields: vec![],
+            unescaped: vec![],
+        })
+    }
+
+    /// Splits the row starting at `pos` into `fields`. Returns the position after the row
+    /// terminator and whether the row is malformed, or `None` when the row is incomplete.
+    /// With `at_eof` the end of `buf` terminates the row. An incomplete row is resumed where
+    /// it stopped by the next call, which must pass the same row extended with more input.
+    fn tokenize_row(&mut self, buf: &[u8], pos: usize, at_eof: bool) -> Option<(usize, bool)> {
+        let mut row = match self.partial.take() {
+            Some(row) => row,
+            None => {
+                self.fields.clear();
+                Partial {
+                    field: None,
+                    scan_from: pos,
+                    in_quotes: false,
+                    malformed: false,
+                }
+            }
+        };
+        loop {
+            let mut field = match row.field {
+                Some(field) => field,
+                None => {
+                    let pos = row.scan_from;
+                    if pos == buf.len() && !at_eof {
+                        // Whether the field is quoted is only known once it has a byte.
+                        self.partial = Some(row);
+                        return None;
+                    }
+                    let quoted = buf.get(pos) == Some(&self.quote);
+                    row.in_quotes = quoted;
+                    row.scan_from = pos + quoted as usize;
+                    Field {
+                        start: row.scan_from,
+                        end: pos,
+                        quoted,
+                        escaped: false,
+                    }
+                }
+            };
+            while row.in_quotes {
+                match find_byte(buf, row.scan_from, self.quote) {
+                    Some(q) if q + 1 < buf.len() && buf[q + 1] == self.quote => {
+                        field.escaped = true;
+                        row.scan_from = q + 2;
+                    }
+                    // A quote at the very end of the chunk may be the first of a pair.
+                    Some(q) if q + 1 < buf.len() || at_eof => {
+                        field.end = q;
+                        row.scan_from = q + 1;
+                        row.in_quotes = false;
+                    }
+                    _ if at_eof => {
+                        // Unterminated quote, the field runs to the end of input.
+                        field.end = buf.len();
+                        row.scan_from = buf.len();
+                        row.malformed = true;
+                        row.in_quotes = false;
+                    }
+                    q => {
+                        row.scan_from = q.unwrap_or(buf.len());
+                        row.field = Some(field);
+                        self.partial = Some(row);
+                        return None;
+                    }
+                }
+            }
+
+            let next = find_any(buf, row.scan_from, [self.delimiter, b'\n', b'\r']);
+            if !field.quoted {
+                field.end = next.unwrap_or(buf.len());
+            } else if next.unwrap_or(buf.len()) != row.scan_from {
+                // Characters between the closing quote and the delimiter are dropped.
+                row.malformed = true;
+            }
+
+            match next {
+                Some(n) if buf[n] == self.delimiter => {
+                    self.fields.push(field);
+                    row.field = None;
+                    row.scan_from = n + 1;
+                }
+                Some(n) if buf[n] == b'\n' => {
+                    self.fields.push(field);
+                    return Some((n + 1, row.malformed));
+                }
+                Some(n) if n + 1 < buf.len() => {
+                    self.fields.push(field);
+                    let end = if buf[n + 1] == b'\n' { n + 2 } else { n + 1 };
+                    return Some((end, row.malformed));
+                }
+                _ if at_eof => {
+                    self.fields.push(field);
+                    return Some((buf.len(), row.malformed));
+                }
+                // Either the end of input or a '\r' at the end of the chunk, which may be
+                // followed by '\n' in the next one.
+                _ => {
+                    row.scan_from = next.unwrap_or(buf.len());
+                    row.field = Some(field);
+                    self.partial = Some(row);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Moves the positions of the incomplete row back by `by` bytes, after the input before
+    /// it has been dropped.
+    fn shift_partial(&mut self, by: usize) {
+        let Some(row) = &mut self.partial else {
+            return;
+        };
+        row.scan_from -= by;
+        let fields = self.fields.iter_mut().chain(row.field.as_mut());
+        for field in fields {
+            field.start -= by;
+            field.end -= by;
+        }
+    }
+
+    fn append_row(&mut self, buf: &[u8], malformed: bool) {
+        if let [f] = self.fields[..] {
+            if !f.quoted && f.start == f.end {
+                // blank line, also before the header
+                return;
+            }
+        }
+        if self.skip_header {
+            self.skip_header = false;
+            return;
+        }
+        let mut error = malformed || self.fields.len() != self.columns.len();
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            let ok = match self.fields.get(i) {
+                Some(f) if f.escaped => {
+                    unescape(&buf[f.start..f.end], self.quote, &mut self.unescaped);
+                    column.push(&self.unescaped, true)
+                }
+                Some(f) => column.push(&buf[f.start..f.end], f.quoted),
+                None => column.push(&[], false),
+            };
+            if !ok {
+                column.errors += 1;
+                error = true;
+            }
+        }
+        self.row_errors.push(error as u8);
+        self.row_count += 1;
+    }
+
+    /// Appends all complete rows of `buf`, returns the number of bytes consumed.
+    fn parse_rows(&mut self, buf: &[u8], at_eof: bool) -> usize {
+        let mut pos = 0;
+        while pos < buf.len() {
+            match self.tokenize_row(buf, pos, at_eof) {
+                Some((next, malformed)) => {
+                    self.append_row(buf, malformed);
+                    pos = next;
+                }
+                None => break,
+            }
+        }
+        pos
+    }
+
+    /// Parses the next chunk of input. Returns the number of rows in the current batch.
+    pub fn parse(&mut self, chunk: &[u8]) -> usize {
+        let mut pos = 0;
+        if !self.carry.is_empty() {
+            // Complete the carried over row, copying no more of the chunk than it needs.
+            // Tokenizing resumes where the previous line left off, so each byte is scanned once.
+            loop {
+                let line_end = find_any(chunk, pos, [b'\n', b'\r']).map_or(chunk.len(), |n| n + 1);
+                self.carry.extend_from_slice(&chunk[pos..line_end]);
+                pos = line_end;
+                let carry = std::mem::take(&mut self.carry);
+                let row = self.tokenize_row(&carry, 0, false);
+                if let Some((next, malformed)) = row {
+                    self.append_row(&carry, malformed);
+                    // The row may end before the copied bytes, e.g. at a '\r' line end.
+                    pos -= carry.len() - next;
+                    self.carry = carry;
+                    self.carry.clear();
+                    break;
+                }
+                self.carry = carry;
+                if pos == chunk.len() {
+                    return self.row_count;
+                }
+            }
+        }
+        let rows_size = self.parse_rows(&chunk[pos..], false);
+        self.shift_partial(rows_size);
+        self.carry.extend_from_slice(&chunk[pos + rows_size..]);
+        self.row_count
+    }
+
+    /// Parses the row left over at the end of input. Returns the number of rows in the
+    /// current batch.
+    pub fn finish(&mut self) -> usize {
+        let carry = std::mem::take(&mut self.carry);
+        self.parse_rows(&carry, true);
+        self.carry = carry;
+        self.carry.clear();
+        self.row_count
+    }
+
+    fn column(&self, index: jint) -> Option<&Column> {
+        self.columns.get(usize::try_from(index).ok()?)
+    }
+
+    /// Starts a new batch, the carried over row is kept.
+    pub fn clear(&mut self) {
+        self.columns.iter_mut().for_each(Column::clear);
+        self.row_errors.clear();
+        self.row_count = 0;
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_create(
+    _env: JNIEnv,
+    _class: JClass,
+    delimiter: jbyte,
+    quote: jbyte,
+    skip_header: jboolean,
+    column_types: *const i32,
+    column_count: jint,
+) -> *mut CsvParser {
+    catch_panic(|| {
+        let column_types = unsafe { slice(column_types, column_count as jlong) };
+        match CsvParser::new(delimiter as u8, quote as u8, skip_header != 0, column_types) {
+            Some(parser) => Box::into_raw(Box::new(parser)),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_destroy(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut CsvParser,
+) {
+    catch_panic(|| {
+        if !parser.is_null() {
+            drop(unsafe { Box::from_raw(parser) });
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_parse(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut CsvParser,
+    lo: *const u8,
+    size: jlong,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &mut *parser };
+        parser.parse(unsafe { slice(lo, size) }) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_finish(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut CsvParser,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &mut *parser };
+        parser.finish() as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_clear(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut CsvParser,
+) {
+    catch_panic(|| {
+        let parser = unsafe { &mut *parser };
+        parser.clear();
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_rowErrors(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const CsvParser,
+) -> *const u8 {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.row_errors.as_ptr()
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_columnData(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const CsvParser,
+    column_index: jint,
+) -> *const u8 {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser
+            .column(column_index)
+            .map_or(std::ptr::null(), |c| c.data().0)
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_columnDataSize(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const CsvParser,
+    column_index: jint,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser
+            .column(column_index)
+            .map_or(-1, |c| c.data().1 as jlong)
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_columnAux(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const CsvParser,
+    column_index: jint,
+) -> *const u8 {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser
+            .column(column_index)
+            .map_or(std::ptr::null(), Column::aux)
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_CsvParserNative_columnErrors(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const CsvParser,
+    column_index: jint,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.column(column_index).map_or(-1, |c| c.errors)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column;
+    use crate::column_type::{INT, STRING, SYMBOL, VARCHAR};
+
+    fn varchars(parser: &CsvParser, column: usize) -> Vec<Option<String>> {
+        let Values::Varchar { aux, data } = &parser.columns[column].values else {
+            panic!("not a VARCHAR column");
+        };
+        aux.iter()
+            .map(|&[offset, len]| {
+                (len >= 0).then(|| {
+                    let bytes = &data[offset as usize..(offset + len) as usize];
+                    String::from_utf8(bytes.to_vec()).unwrap()
+                })
+            })
+            .collect()
+    }
+
+    fn parse_chunks(input: &[u8], chunk_size: usize) -> CsvParser {
+        let mut parser = CsvParser::new(b',', b'"', false, &[VARCHAR, VARCHAR]).unwrap();
+        for chunk in input.chunks(chunk_size) {
+            parser.parse(chunk);
+        }
+        parser.finish();
+        parser
+    }
+
+    fn rows(parser: &CsvParser) -> Vec<[Option<String>; 2]> {
+        varchars(parser, 0)
+            .into_iter()
+            .zip(varchars(parser, 1))
+            .map(|(a, b)| [a, b])
+            .collect()
+    }
+
+    fn row(a: Option<&str>, b: Option<&str>) -> [Option<String>; 2] {
+        [a.map(String::from), b.map(String::from)]
+    }
+
+    const INPUT: &[u8] =
+        b"a,\"b,c\"\r\n\"say \"\"hi\"\"\",\r\n\"\",\"two\nlines\"\r\n\rlast,\"\"\"\"";
+
+    #[test]
+    fn quoting_and_line_ends() {
+        let expected = [
+            row(Some("a"), Some("b,c")),
+            row(Some("say \"hi\""), None),
+            row(Some(""), Some("two\nlines")),
+            row(Some("last"), Some("\"")),
+        ];
+        let parser = parse_chunks(INPUT, INPUT.len());
+        assert_eq!(rows(&parser), expected);
+        assert_eq!(parser.row_errors, [0; 4]);
+    }
+
+    #[test]
+    fn rows_carry_across_chunks() {
+        let expected = rows(&parse_chunks(INPUT, INPUT.len()));
+        for chunk_size in 1..INPUT.len() {
+            let parser = parse_chunks(INPUT, chunk_size);
+            assert_eq!(rows(&parser), expected, "chunk size {chunk_size}");
+            assert_eq!(parser.row_errors, [0; 4], "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn malformed_rows() {
+        let input = b"\"a\"x,b\nc\n\"open,d";
+        for chunk_size in 1..=input.len() {
+            let parser = parse_chunks(input, chunk_size);
+            assert_eq!(
+                rows(&parser),
+                [
+                    row(Some("a"), Some("b")),
+                    row(Some("c"), None),
+                    row(Some("open,d"), None),
+                ],
+                "chunk size {chunk_size}"
+            );
+            assert_eq!(parser.row_errors, [1, 1, 1], "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn typed_columns_and_header() {
+        let mut parser = CsvParser::new(b';', b'"', true, &[INT, VARCHAR]).unwrap();
+        assert_eq!(parser.parse(b"n;s\n1;x\n\n;y\nbad;z\n"), 3);
+        let Values::Int(ints) = &parser.columns[0].values else {
+            panic!("not an INT column");
+        };
+        assert_eq!(ints, &[1, INT_NULL, INT_NULL]);
+        assert_eq!(parser.columns[0].errors, 1);
+        assert_eq!(parser.row_errors, [0, 0, 1]);
+        parser.clear();
+        assert_eq!(parser.finish(), 0);
+        assert!(CsvParser::new(b',', b'"', false, &[crate::column_type::UUID]).is_none());
+    }
+
+    #[test]
+    fn blank_lines_before_header() {
+        let mut parser = CsvParser::new(b',', b'"', true, &[INT, VARCHAR]).unwrap();
+        assert_eq!(parser.parse(b"\n\r\nn,s\n1,x\n"), 1);
+        let Values::Int(ints) = &parser.columns[0].values else {
+            panic!("not an INT column");
+        };
+        assert_eq!(ints, &[1]);
+        assert_eq!(varchars(&parser, 1), [Some("x".to_string())]);
+        assert_eq!(parser.row_errors, [0]);
+        // Only a row with fields is taken as the header, also across chunks.
+        let mut parser = CsvParser::new(b',', b'"', true, &[INT, VARCHAR]).unwrap();
+        for chunk in [&b"\r"[..], b"\n", b"\nn,", b"s\n2,y\n"] {
+            parser.parse(chunk);
+        }
+        assert_eq!(parser.finish(), 1);
+        assert_eq!(varchars(&parser, 1), [Some("y".to_string())]);
+    }
+
+    #[test]
+    fn string_and_symbol_columns() {
+        let mut parser = CsvParser::new(b',', b'"', false, &[STRING, SYMBOL]).unwrap();
+        let input = b"h\xc3\xa9,a\n,\n\"\",\"\"\n\xff,\xff\n\"\xf0\x9f\x98\x80\",b\n";
+        assert_eq!(parser.parse(input), 5);
+        // STRING rows in the storage layout, as the STRING columns of `ColumnDesc`.
+        let (data, aux) =
+            column::string_column(&[Some("h\u{e9}"), None, Some(""), None, Some("\u{1f600}")]);
+        let strings = &parser.columns[0];
+        let (ptr, size) = strings.data();
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, size) }, &data[..]);
+        let Values::String { aux: offsets, .. } = &strings.values else {
+            panic!("not a STRING column");
+        };
+        let offsets: Vec<u8> = offsets.iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(offsets, aux);
+        // SYMBOL values are kept as text.
+        assert_eq!(
+            varchars(&parser, 1),
+            [
+                Some("a".to_string()),
+                None,
+                Some(String::new()),
+                None,
+                Some("b".to_string())
+            ]
+        );
+        assert_eq!(parser.columns[0].errors, 1);
+        assert_eq!(parser.columns[1].errors, 1);
+        assert_eq!(parser.row_errors, [0, 0, 0, 1, 0]);
+        parser.clear();
+        assert_eq!(parser.columns[0].data().1, 0);
+    }
+
+    #[test]
+    fn column_getters_check_index() {
+        let parser = CsvParser::new(b',', b'"', false, &[INT]).unwrap();
+        assert!(parser.column(0).is_some());
+        assert!(parser.column(1).is_none());
+        assert!(parser.column(-1).is_none());
+    }
+}
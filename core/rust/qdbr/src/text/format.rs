@@ -0,0 +1,416 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Text formatting of column values into byte buffers, matching the text representation
+//! used by the Java side (`Numbers`, `GeoHashes`, ISO timestamps with a `Z` suffix).
+
+use std::io::Write;
+
+use super::timestamp::{civil_from_days, MICROS_PER_DAY};
+
+const DIGIT_PAIRS: &[u8; 200] = b"\
+    0001020304050607080910111213141516171819\
+    2021222324252627282930313233343536373839\
+    4041424344454647484950515253545556575859\
+    6061626364656667686970717273747576777879\
+    8081828384858687888990919293949596979899";
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+const GEOHASH_BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+pub fn push_u64(out: &mut Vec<u8>, mut v: u64) {
+    let mut buf = [0u8; 20];
+    let mut pos = buf.len();
+    while v >= 100 {
+        let pair = (v % 100) as usize * 2;
+        v /= 100;
+        pos -= 2;
+        buf[pos..pos + 2].copy_from_slice(&DIGIT_PAIRS[pair..pair + 2]);
+    }
+    if v >= 10 {
+        pos -= 2;
+        buf[pos..pos + 2].copy_from_slice(&DIGIT_PAIRS[v as usize * 2..v as usize * 2 + 2]);
+    } else {
+        pos -= 1;
+        buf[pos] = b'0' + v as u8;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+pub fn push_i64(out: &mut Vec<u8>, v: i64) {
+    if v < 0 {
+        out.push(b'-');
+    }
+    push_u64(out, v.unsigned_abs());
+}
+
+/// Formats `v` zero-padded to `width` digits.
+fn push_padded(out: &mut Vec<u8>, v: u32, width: usize) {
+    let start = out.len();
+    push_u64(out, v as u64);
+    let len = out.len() - start;
+    if len < width {
+        out.splice(start..start, std::iter::repeat_n(b'0', width - len));
+    }
+}
+
+/// Shortest representation that round-trips, laid out as `Numbers.append(double)` does:
+/// `0.001` to `9999999.0` in plain notation, other magnitudes as `1.0E-4` or `1.0E7`. Java
+/// prints more digits for about 1% of values, mostly integers above 2^53, both texts parse
+/// back to the same double. Callers handle NaN and infinities as null.
+pub fn push_f64(out: &mut Vec<u8>, v: f64) {
+    let mut buf = [0u8; 32];
+    let mut cursor = &mut buf[..];
+    write!(cursor, "{:e}", v.abs()).unwrap();
+    let len = 32 - cursor.len();
+    push_java_float(out, v.is_sign_negative(), &buf[..len]);
+}
+
+/// Shortest representation that round-trips, in the layout of `Float.toString()`.
+pub fn push_f32(out: &mut Vec<u8>, v: f32) {
+    let mut buf = [0u8; 32];
+    let mut cursor = &mut buf[..];
+    write!(cursor, "{:e}", v.abs()).unwrap();
+    let len = 32 - cursor.len();
+    push_java_float(out, v.is_sign_negative(), &buf[..len]);
+}
+
+/// Lays out the `d[.ddd]e[-]x` text of a non-negative number as Java's `Double.toString()`.
+fn push_java_float(out: &mut Vec<u8>, negative: bool, sci: &[u8]) {
+    let e = sci.iter().position(|&b| b == b'e').unwrap();
+    let exp: i32 = std::str::from_utf8(&sci[e + 1..]).unwrap().parse().unwrap();
+    let mut digits = [0u8; 24];
+    let mut n = 0;
+    for &b in sci[..e].iter().filter(|&&b| b != b'.') {
+        digits[n] = b;
+        n += 1;
+    }
+    let digits = &digits[..n];
+    if negative {
+        out.push(b'-');
+    }
+    match exp {
+        0..=6 => {
+            let int = exp as usize + 1;
+            if digits.len() > int {
+                out.extend_from_slice(&digits[..int]);
+                out.push(b'.');
+                out.extend_from_slice(&digits[int..]);
+            } else {
+                out.extend_from_slice(digits);
+                out.extend(std::iter::repeat_n(b'0', int - digits.len()));
+                out.extend_from_slice(b".0");
+            }
+        }
+        -3..=-1 => {
+            out.extend_from_slice(b"0.");
+            out.extend(std::iter::repeat_n(b'0', (-exp - 1) as usize));
+            out.extend_from_slice(digits);
+        }
+        _ => {
+            out.push(digits[0]);
+            out.push(b'.');
+            if digits.len() > 1 {
+                out.extend_from_slice(&digits[1..]);
+            } else {
+                out.push(b'0');
+            }
+            out.push(b'E');
+            push_i64(out, exp as i64);
+        }
+    }
+}
+
+fn push_date_time(out: &mut Vec<u8>, micros: i64) -> i64 {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let micros_of_day = micros.rem_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    if year < 0 {
+        out.push(b'-');
+    }
+    push_padded(out, year.unsigned_abs() as u32, 4);
+    out.push(b'-');
+    push_padded(out, month, 2);
+    out.push(b'-');
+    push_padded(out, day, 2);
+    out.push(b'T');
+    let seconds = micros_of_day / 1_000_000;
+    push_padded(out, (seconds / 3600) as u32, 2);
+    out.push(b':');
+    push_padded(out, (seconds / 60 % 60) as u32, 2);
+    out.push(b':');
+    push_padded(out, (seconds % 60) as u32, 2);
+    micros_of_day % 1_000_000
+}
+
+/// Long scaled by `10^scale`, with exactly `scale` fraction digits.
+pub fn push_decimal(out: &mut Vec<u8>, v: i64, scale: u32) {
+    let pow = 10u64.pow(scale);
+    let magnitude = v.unsigned_abs();
+    if v < 0 {
+        out.push(b'-');
+    }
+    push_u64(out, magnitude / pow);
+    if scale > 0 {
+        out.push(b'.');
+        let at = out.len();
+        push_u64(out, magnitude % pow);
+        let zeros = scale as usize - (out.len() - at);
+        out.splice(at..at, std::iter::repeat_n(b'0', zeros));
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.ffffffZ`
+pub fn push_timestamp(out: &mut Vec<u8>, micros: i64) {
+    let fraction = push_date_time(out, micros);
+    out.push(b'.');
+    push_padded(out, fraction as u32, 6);
+    out.push(b'Z');
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.fffZ`
+pub fn push_date(out: &mut Vec<u8>, millis: i64) {
+    let fraction = push_date_time(out, millis.saturating_mul(1000));
+    out.push(b'.');
+    push_padded(out, (fraction / 1000) as u32, 3);
+    out.push(b'Z');
+}
+
+pub fn push_ipv4(out: &mut Vec<u8>, ip: u32) {
+    for (i, octet) in ip.to_be_bytes().into_iter().enumerate() {
+        if i > 0 {
+            out.push(b'.');
+        }
+        push_u64(out, octet as u64);
+    }
+}
+
+fn push_hex(out: &mut Vec<u8>, v: u64, digits: u32) {
+    for i in (0..digits).rev() {
+        out.push(HEX_DIGITS[(v >> (i * 4)) as usize & 0xF]);
+    }
+}
+
+/// Canonical `8-4-4-4-12` form of a UUID stored as `(lo, hi)` longs.
+pub fn push_uuid(out: &mut Vec<u8>, lo: u64, hi: u64) {
+    push_hex(out, hi >> 32, 8);
+    out.push(b'-');
+    push_hex(out, hi >> 16, 4);
+    out.push(b'-');
+    push_hex(out, hi, 4);
+    out.push(b'-');
+    push_hex(out, lo >> 48, 4);
+    out.push(b'-');
+    push_hex(out, lo, 12);
+}
+
+/// `0x` followed by the hex digits of the long256, most significant word first, without
+/// leading zero bytes.
+pub fn push_long256(out: &mut Vec<u8>, words: &[u64; 4]) {
+    out.extend_from_slice(b"0x");
+    let top = words.iter().rposition(|&w| w != 0).unwrap_or(0);
+    let top_bytes = (64 - words[top].leading_zeros()).div_ceil(8).max(1);
+    push_hex(out, words[top], top_bytes * 2);
+    for &w in words[..top].iter().rev() {
+        push_hex(out, w, 16);
+    }
+}
+
+/// Geohashes with a precision that is a multiple of 5 bits are printed as base32 chars,
+/// others as a binary string.
+pub fn push_geohash(out: &mut Vec<u8>, hash: i64, bits: u32) {
+    if bits.is_multiple_of(5) {
+        for i in (0..bits / 5).rev() {
+            out.push(GEOHASH_BASE32[(hash >> (i * 5)) as usize & 0x1F]);
+        }
+    } else {
+        for i in (0..bits).rev() {
+            out.push(if (hash >> i) & 1 == 1 { b'1' } else { b'0' });
+        }
+    }
+}
+
+/// Appends a quoted JSON string, escaping quotes, backslashes and control characters.
+pub fn push_json_str(out: &mut Vec<u8>, s: &[u8]) {
+    out.push(b'"');
+    let mut run = 0;
+    for (i, &b) in s.iter().enumerate() {
+        let escape: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0C => b"\\f",
+            0..=0x1F => b"",
+            _ => continue,
+        };
+        out.extend_from_slice(&s[run..i]);
+        if escape.is_empty() {
+            out.extend_from_slice(b"\\u00");
+            push_hex(out, b as u64, 2);
+        } else {
+            out.extend_from_slice(escape);
+        }
+        run = i + 1;
+    }
+    out.extend_from_slice(&s[run..]);
+    out.push(b'"');
+}
+
+/// Converts little-endian UTF-16 bytes to UTF-8, replacing unpaired surrogates.
+pub fn push_utf16(out: &mut Vec<u8>, utf16_le: &[u8]) {
+    let units = utf16_le
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]));
+    for c in char::decode_utf16(units) {
+        let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+        out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(push: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut out = Vec::new();
+        push(&mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn doubles_as_java() {
+        // Numbers.append(double) output.
+        let cases = [
+            (1e16, "1.0E16"),
+            (1e-7, "1.0E-7"),
+            (-1e-7, "-1.0E-7"),
+            (1.5, "1.5"),
+            (100.0, "100.0"),
+            (1e7, "1.0E7"),
+            (9999999.0, "9999999.0"),
+            (12345678.9, "1.23456789E7"),
+            (0.001, "0.001"),
+            (0.00123, "0.00123"),
+            (1e-4, "1.0E-4"),
+            (5e-5, "5.0E-5"),
+            (123.456, "123.456"),
+            (0.1, "0.1"),
+            (1.0 / 3.0, "0.3333333333333333"),
+            (123456789012345678.0, "1.2345678901234568E17"),
+            (1.2345e-10, "1.2345E-10"),
+            (f64::MAX, "1.7976931348623157E308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014E-308"),
+            (0.0, "0.0"),
+            (-0.0, "-0.0"),
+        ];
+        for (v, expected) in cases {
+            assert_eq!(text(|out| push_f64(out, v)), expected);
+        }
+    }
+
+    #[test]
+    fn digits_are_shortest() {
+        // Numbers.append(double) prints 4.9E-324, 9.999999999999999E22 and 5.0874350232723688E16.
+        let cases = [
+            (4.9e-324, "5.0E-324"),
+            (1e23, "1.0E23"),
+            (5.087435023272369e16, "5.087435023272369E16"),
+        ];
+        for (v, expected) in cases {
+            assert_eq!(text(|out| push_f64(out, v)), expected);
+        }
+        // Float.toString() prints 1.4E-45.
+        assert_eq!(text(|out| push_f32(out, f32::from_bits(1))), "1.0E-45");
+    }
+
+    #[test]
+    fn floats_as_java() {
+        let cases = [
+            (1.5f32, "1.5"),
+            (1e7, "1.0E7"),
+            (1e-7, "1.0E-7"),
+            (0.001, "0.001"),
+            (0.1, "0.1"),
+            (1.0 / 3.0, "0.33333334"),
+            (123456.79, "123456.79"),
+            (f32::MAX, "3.4028235E38"),
+            (-2.0, "-2.0"),
+        ];
+        for (v, expected) in cases {
+            assert_eq!(text(|out| push_f32(out, v)), expected);
+        }
+    }
+
+    #[test]
+    fn json_str() {
+        let json = |s: &[u8]| text(|out| push_json_str(out, s));
+        assert_eq!(json(b""), r#""""#);
+        assert_eq!(json(b"plain"), r#""plain""#);
+        assert_eq!(json(br#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(
+            json(b"\n\r\t\x08\x0c\x00\x01\x1f\x7f"),
+            "\"\\n\\r\\t\\b\\f\\u0000\\u0001\\u001f\x7f\""
+        );
+        assert_eq!(json("é/€".as_bytes()), "\"é/€\"");
+    }
+
+    #[test]
+    fn utf16() {
+        let utf16 = |units: &[u16]| {
+            let bytes: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).collect();
+            text(|out| push_utf16(out, &bytes))
+        };
+        assert_eq!(utf16(&[0x61, 0xe9, 0x20ac]), "aé€");
+        assert_eq!(utf16(&[0xd83d, 0xde00]), "😀");
+        // Unpaired surrogates.
+        assert_eq!(utf16(&[0xd83d, 0x61]), "\u{fffd}a");
+        assert_eq!(utf16(&[0xde00]), "\u{fffd}");
+    }
+
+    #[test]
+    fn timestamps_and_dates() {
+        assert_eq!(
+            text(|out| push_timestamp(out, 0)),
+            "1970-01-01T00:00:00.000000Z"
+        );
+        assert_eq!(
+            text(|out| push_timestamp(out, -1)),
+            "1969-12-31T23:59:59.999999Z"
+        );
+        assert_eq!(
+            text(|out| push_timestamp(out, 1_709_210_096_123_456)),
+            "2024-02-29T12:34:56.123456Z"
+        );
+        assert_eq!(text(|out| push_date(out, 0)), "1970-01-01T00:00:00.000Z");
+        assert_eq!(text(|out| push_date(out, -1)), "1969-12-31T23:59:59.999Z");
+        assert_eq!(
+            text(|out| push_date(out, 951_782_400_000)),
+            "2000-02-29T00:00:00.000Z"
+        );
+    }
+}
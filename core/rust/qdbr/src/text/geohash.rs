@@ -0,0 +1,402 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Batch geohash conversions, matching `GeoHashes`: from latitude/longitude degrees, to the
+//! center of the cell, and between geohash bits and text.
+//!
+//! Geohash buffers use the column storage width of the precision, one byte up to 7 bits, a
+//! short up to 15 bits, an int up to 31 bits and a long up to 60 bits, with -1 for null.
+
+use jni::objects::JClass;
+use jni::sys::{jdouble, jint, jlong};
+use jni::JNIEnv;
+
+use super::convert::{field, write_text, AuxEntry};
+use super::format;
+use crate::column_type::GEOHASH_NULL;
+use crate::diagnostics::catch_panic;
+use crate::mem::{slice, slice_mut};
+
+pub const MAX_BITS: u32 = 60;
+/// Longest base32 text accepted when parsing, as `GeoHashes.MAX_STRING_LENGTH`.
+const MAX_CHARS: usize = 12;
+
+pub fn storage_size(bits: u32) -> usize {
+    match bits {
+        0..=7 => 1,
+        8..=15 => 2,
+        16..=31 => 4,
+        _ => 8,
+    }
+}
+
+fn load(buf: &[u8], i: usize, size: usize) -> i64 {
+    let v = &buf[i * size..(i + 1) * size];
+    match size {
+        1 => v[0] as i8 as i64,
+        2 => i16::from_le_bytes(v.try_into().unwrap()) as i64,
+        4 => i32::from_le_bytes(v.try_into().unwrap()) as i64,
+        _ => i64::from_le_bytes(v.try_into().unwrap()),
+    }
+}
+
+fn store(buf: &mut [u8], i: usize, size: usize, v: i64) {
+    buf[i * size..(i + 1) * size].copy_from_slice(&v.to_le_bytes()[..size]);
+}
+
+fn spread_bits(mut v: u64) -> u64 {
+    v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+    v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    (v | (v << 1)) & 0x5555_5555_5555_5555
+}
+
+fn compact_bits(mut v: u64) -> u64 {
+    v &= 0x5555_5555_5555_5555;
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v >> 4)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v >> 8)) & 0x0000_FFFF_0000_FFFF;
+    (v | (v >> 16)) & 0x0000_0000_FFFF_FFFF
+}
+
+/// Geohash of a point, `None` when the coordinates are out of range or NaN. Longitude takes
+/// the first bit, as in `GeoHashes.fromCoordinatesDeg()`.
+pub fn from_coordinates(lat: f64, lon: f64, bits: u32) -> Option<i64> {
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let lat_q = ((lat + 90.0) / 180.0 * 4_294_967_296.0) as i64 as u64;
+    let lon_q = ((lon + 180.0) / 360.0 * 4_294_967_296.0) as i64 as u64;
+    let interleaved = spread_bits(lat_q) | (spread_bits(lon_q) << 1);
+    Some((interleaved >> (64 - bits)) as i64)
+}
+
+/// Latitude and longitude of the center of a geohash cell.
+pub fn to_coordinates(hash: i64, bits: u32) -> (f64, f64) {
+    let interleaved = (hash as u64) << (64 - bits);
+    let lat_bits = bits / 2;
+    let lon_bits = bits - lat_bits;
+    let lat_q = compact_bits(interleaved) + (1u64 << (31 - lat_bits));
+    let lon_q = compact_bits(interleaved >> 1) + (1u64 << (31 - lon_bits));
+    (
+        lat_q as f64 / 4_294_967_296.0 * 180.0 - 90.0,
+        lon_q as f64 / 4_294_967_296.0 * 360.0 - 180.0,
+    )
+}
+
+fn base32_index(b: u8) -> Option<i64> {
+    let i = match b.to_ascii_lowercase() {
+        c @ b'0'..=b'9' => c - b'0',
+        c @ b'b'..=b'h' => c - b'b' + 10,
+        b'j' => 17,
+        b'k' => 18,
+        b'm' => 19,
+        b'n' => 20,
+        c @ b'p'..=b'z' => c - b'p' + 21,
+        _ => return None,
+    };
+    Some(i as i64)
+}
+
+/// Parses base32 text of at least `bits` precision, truncating it to `bits` as
+/// `GeoHashes.fromAsciiTruncatingNl()` does. Characters past the 12th are ignored.
+pub fn parse(text: &[u8], bits: u32) -> Option<i64> {
+    let text = &text[..text.len().min(MAX_CHARS)];
+    let text_bits = 5 * text.len() as u32;
+    if text_bits < bits {
+        return None;
+    }
+    let hash = text
+        .iter()
+        .try_fold(0i64, |h, &b| Some((h << 5) | base32_index(b)?))?;
+    Some(hash >> (text_bits - bits))
+}
+
+fn valid_bits(bits: jint) -> Option<u32> {
+    (1..=MAX_BITS as jint)
+        .contains(&bits)
+        .then_some(bits as u32)
+}
+
+/// Returns the number of points out of range, which are written as null, or -1 on invalid
+/// precision.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_encodeGeoHash(
+    _env: JNIEnv,
+    _class: JClass,
+    lat: *const f64,
+    lon: *const f64,
+    count: jlong,
+    bits: jint,
+    out: *mut u8,
+) -> jlong {
+    catch_panic(|| {
+        let Some(bits) = valid_bits(bits) else {
+            return -1;
+        };
+        let size = storage_size(bits);
+        let lat = unsafe { slice(lat, count) };
+        let lon = unsafe { slice(lon, count) };
+        let out = unsafe { slice_mut(out, count * size as jlong) };
+        let mut errors = 0;
+        for (i, (&lat, &lon)) in lat.iter().zip(lon).enumerate() {
+            let hash = from_coordinates(lat, lon, bits).unwrap_or_else(|| {
+                errors += 1;
+                GEOHASH_NULL
+            });
+            store(out, i, size, hash);
+        }
+        errors
+    })
+}
+
+/// Writes the cell centers, NaN for nulls. Returns 0, or -1 on invalid precision.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_decodeGeoHash(
+    _env: JNIEnv,
+    _class: JClass,
+    hashes: *const u8,
+    count: jlong,
+    bits: jint,
+    lat_out: *mut jdouble,
+    lon_out: *mut jdouble,
+) -> jlong {
+    catch_panic(|| {
+        let Some(bits) = valid_bits(bits) else {
+            return -1;
+        };
+        let size = storage_size(bits);
+        let hashes = unsafe { slice(hashes, count * size as jlong) };
+        let lat_out = unsafe { slice_mut(lat_out, count) };
+        let lon_out = unsafe { slice_mut(lon_out, count) };
+        for (i, (lat, lon)) in lat_out.iter_mut().zip(lon_out).enumerate() {
+            (*lat, *lon) = match load(hashes, i, size) {
+                GEOHASH_NULL => (f64::NAN, f64::NAN),
+                hash => to_coordinates(hash, bits),
+            };
+        }
+        0
+    })
+}
+
+/// Returns the number of unparsable values, which are written as null, or -1 on invalid
+/// precision. Empty text is null.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_parseGeoHash(
+    _env: JNIEnv,
+    _class: JClass,
+    data: *const u8,
+    data_size: jlong,
+    aux: *const AuxEntry,
+    count: jlong,
+    bits: jint,
+    out: *mut u8,
+) -> jlong {
+    catch_panic(|| {
+        let Some(bits) = valid_bits(bits) else {
+            return -1;
+        };
+        let size = storage_size(bits);
+        let data = unsafe { slice(data, data_size) };
+        let aux = unsafe { slice(aux, count) };
+        let out = unsafe { slice_mut(out, count * size as jlong) };
+        let mut errors = 0;
+        for (i, &entry) in aux.iter().enumerate() {
+            let hash = match field(data, entry) {
+                Ok(None) => GEOHASH_NULL,
+                Ok(Some([])) => GEOHASH_NULL,
+                Ok(Some(text)) => parse(text, bits).unwrap_or_else(|| {
+                    errors += 1;
+                    GEOHASH_NULL
+                }),
+                Err(()) => {
+                    errors += 1;
+                    GEOHASH_NULL
+                }
+            };
+            store(out, i, size, hash);
+        }
+        errors
+    })
+}
+
+/// Formats as `format::push_geohash()`, `bits` bytes per value at most, with
+/// `convert::write_text()`. Returns the data size, which is larger than `data_capacity` when not
+/// all values were written, or -1 on invalid precision.
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_text_TextConvertNative_formatGeoHash(
+    _env: JNIEnv,
+    _class: JClass,
+    hashes: *const u8,
+    count: jlong,
+    bits: jint,
+    data_out: *mut u8,
+    data_capacity: jlong,
+    aux_out: *mut AuxEntry,
+) -> jlong {
+    catch_panic(|| {
+        let Some(bits) = valid_bits(bits) else {
+            return -1;
+        };
+        let size = storage_size(bits);
+        let hashes = unsafe { slice(hashes, count * size as jlong) };
+        let size = unsafe {
+            write_text(count, data_out, data_capacity, aux_out, |out, i| {
+                let hash = load(hashes, i, size);
+                if hash == GEOHASH_NULL {
+                    return Ok(false);
+                }
+                format::push_geohash(out, hash, bits);
+                Ok(true)
+            })
+        };
+        size.unwrap_or(-1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(lat, lon, bits, hash, text)` from `GeoHashes.fromCoordinatesDeg()` and
+    /// `GeoHashes.appendChars()` or `appendBinary()`.
+    const POINTS: &[(f64, f64, u32, i64, &str)] = &[
+        (52.52, 13.405, 1, 1, "1"),
+        (52.52, 13.405, 7, 104, "1101000"),
+        (52.52, 13.405, 15, 26723, "u33"),
+        (
+            52.52,
+            13.405,
+            32,
+            3502687617,
+            "11010000110001101100010110000001",
+        ),
+        (52.52, 13.405, 60, 940245547818338055, "u33dc0cppjs7"),
+        (-33.8688, 151.2093, 3, 5, "101"),
+        (-33.8688, 151.2093, 20, 757245, "r3gx"),
+        (-33.8688, 151.2093, 60, 832599766569668740, "r3gx2f77bn44"),
+        (0.0, 0.0, 5, 24, "s"),
+        (0.0, 0.0, 60, 864691128455135232, "s00000000000"),
+        (37.7749, -122.4194, 7, 38, "0100110"),
+        (37.7749, -122.4194, 20, 317726, "9q8y"),
+        (37.7749, -122.4194, 60, 349344481866995639, "9q8yyk8ytpxr"),
+        (-90.0, -180.0, 60, 0, "000000000000"),
+        (90.0, 180.0, 5, 0, "0"),
+        (90.0, 180.0, 32, 3, "00000000000000000000000000000011"),
+        (90.0, 180.0, 60, 805306368, "000000s00000"),
+    ];
+
+    fn text(hash: i64, bits: u32) -> String {
+        let mut out = Vec::new();
+        format::push_geohash(&mut out, hash, bits);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn matches_geohashes() {
+        for &(lat, lon, bits, hash, expected) in POINTS {
+            assert_eq!(
+                from_coordinates(lat, lon, bits),
+                Some(hash),
+                "{lat} {lon} {bits}"
+            );
+            assert_eq!(text(hash, bits), expected);
+            if bits % 5 == 0 {
+                assert_eq!(parse(expected.as_bytes(), bits), Some(hash));
+            }
+        }
+        assert_eq!(from_coordinates(90.5, 0.0, 5), None);
+        assert_eq!(from_coordinates(0.0, f64::NAN, 5), None);
+    }
+
+    #[test]
+    fn parse_truncates() {
+        // As GeoHashes.fromStringTruncatingNl().
+        assert_eq!(parse(b"u33dc0", 20), Some(855148));
+        assert_eq!(parse(b"U33DC0", 20), Some(855148));
+        assert_eq!(parse(b"u33dc0cpke7vzz", 20), Some(855148));
+        assert_eq!(parse(b"u33", 20), None);
+        assert_eq!(parse(b"u33a", 20), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &(lat, lon, _, _, _) in POINTS {
+            for bits in 1..=MAX_BITS {
+                let hash = from_coordinates(lat, lon, bits).unwrap();
+                // The center of the cell is in the same cell.
+                let (lat, lon) = to_coordinates(hash, bits);
+                assert_eq!(from_coordinates(lat, lon, bits), Some(hash), "{bits}");
+                if bits % 5 == 0 {
+                    assert_eq!(parse(text(hash, bits).as_bytes(), bits), Some(hash));
+                }
+            }
+        }
+    }
+
+    fn format_hashes(
+        hashes: &[i64],
+        bits: u32,
+        capacity: usize,
+    ) -> (jlong, Vec<u8>, Vec<AuxEntry>) {
+        let size = storage_size(bits);
+        let mut stored = vec![0; hashes.len() * size];
+        for (i, &hash) in hashes.iter().enumerate() {
+            store(&mut stored, i, size, hash);
+        }
+        let mut data = vec![0; capacity];
+        let mut aux = vec![[-2; 2]; hashes.len()];
+        let result = Java_io_questdb_cutlass_text_TextConvertNative_formatGeoHash(
+            crate::test_env(),
+            JClass::default(),
+            stored.as_ptr(),
+            hashes.len() as jlong,
+            bits as jint,
+            data.as_mut_ptr(),
+            capacity as jlong,
+            aux.as_mut_ptr(),
+        );
+        (result, data, aux)
+    }
+
+    #[test]
+    fn format_checks_capacity() {
+        let hashes = [26723, GEOHASH_NULL, 0];
+        assert_eq!(
+            format_hashes(&hashes, 15, 6),
+            (6, b"u33000".to_vec(), vec![[0, 3], [3, -1], [3, 3]])
+        );
+        // Writing stops at the value that does not fit.
+        assert_eq!(
+            format_hashes(&hashes, 15, 5),
+            (6, b"u33\0\0".to_vec(), vec![[0, 3], [3, -1], [-2, -2]])
+        );
+        assert_eq!(format_hashes(&[104], 7, 7).1, b"1101000");
+        assert_eq!(format_hashes(&[104], 7, 6).0, 7);
+        assert_eq!(format_hashes(&[0], 61, 64).0, -1);
+        assert_eq!(format_hashes(&[0], 0, 64).0, -1);
+    }
+}
@@ -0,0 +1,716 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Native InfluxDB line protocol parser. Batches of ILP text are split into lines and
+//! tokenized into two native tables: one entry per line and one per tag/field entity.
+//! Names and values are unescaped into a shared string buffer. Entity types, timestamp
+//! units and error codes use the values of `io.questdb.cutlass.line.tcp.LineTcpParser`.
+//!
+//! Lines end at an unescaped `\n` or `\r`, as in `LineTcpParser`, and empty lines are
+//! skipped. Inside a quoted string field value a raw `\r` is part of the value, while a raw
+//! `\n` ends the line, which then fails for the missing closing quote. A line is only parsed
+//! once its terminator is in the buffer, so callers resend the unconsumed tail together with
+//! the next chunk of input.
+
+use jni::objects::JClass;
+use jni::sys::{jboolean, jlong};
+use jni::JNIEnv;
+
+use super::numbers::{parse_bool, parse_f64, parse_i64};
+use super::scan::find_any;
+use crate::column_type::LONG_NULL;
+use crate::diagnostics::catch_panic;
+use crate::mem::slice;
+
+pub const ENTITY_TYPE_TAG: u8 = 1;
+pub const ENTITY_TYPE_FLOAT: u8 = 2;
+pub const ENTITY_TYPE_INTEGER: u8 = 3;
+pub const ENTITY_TYPE_STRING: u8 = 4;
+pub const ENTITY_TYPE_BOOLEAN: u8 = 6;
+pub const ENTITY_TYPE_LONG256: u8 = 7;
+pub const ENTITY_TYPE_TIMESTAMP: u8 = 13;
+
+pub const ENTITY_UNIT_NONE: u8 = 0;
+pub const ENTITY_UNIT_NANO: u8 = 1;
+pub const ENTITY_UNIT_MICRO: u8 = 2;
+pub const ENTITY_UNIT_MILLI: u8 = 3;
+
+/// Ordinals of `LineTcpParser.ErrorCode`.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    NoFields = 1,
+    IncompleteTag = 2,
+    IncompleteField = 3,
+    InvalidFieldSeparator = 4,
+    InvalidTimestamp = 5,
+    InvalidFieldValue = 7,
+    InvalidTableName = 9,
+    InvalidColumnName = 10,
+    MissingFieldValue = 11,
+    MissingTagValue = 12,
+    None = 13,
+}
+
+/// Position of an unescaped string in the batch's string buffer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrRef {
+    pub offset: u32,
+    pub len: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IlpEntity {
+    pub name: StrRef,
+    /// Unescaped value text, for all entity types.
+    pub value: StrRef,
+    pub entity_type: u8,
+    pub unit: u8,
+    _reserved: [u8; 6],
+    /// Integer, boolean (0/1) and timestamp values, or the bits of a float value.
+    pub long_value: i64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IlpLine {
+    pub measurement: StrRef,
+    pub entity_lo: u32,
+    pub entity_count: u32,
+    /// `LONG_NULL` when the line has no timestamp.
+    pub timestamp: i64,
+    pub error_code: ErrorCode,
+    pub timestamp_unit: u8,
+    _reserved: [u8; 3],
+}
+
+/// `Numbers.parseLong` takes no `+` sign.
+fn parse_long(value: &[u8]) -> Option<i64> {
+    if value.first() == Some(&b'+') {
+        return None;
+    }
+    parse_i64(value)
+}
+
+/// `Numbers.parseDouble` only takes `NaN` and `Infinity` spelled out and rejects values out of
+/// range, where the Rust parser also takes `inf` and `nan` in any case and overflows to
+/// infinity.
+fn parse_double(value: &[u8]) -> Option<f64> {
+    match value {
+        b"NaN" => Some(f64::NAN),
+        b"Infinity" => Some(f64::INFINITY),
+        b"-Infinity" => Some(f64::NEG_INFINITY),
+        _ => parse_f64(value).filter(|v| v.is_finite()),
+    }
+}
+
+/// Splits a timestamp into its value and unit suffix, `n`, `t` or `m`.
+fn parse_timestamp(value: &[u8]) -> Option<(i64, u8)> {
+    let (digits, unit) = match value.last()? {
+        b'n' => (&value[..value.len() - 1], ENTITY_UNIT_NANO),
+        b't' => (&value[..value.len() - 1], ENTITY_UNIT_MICRO),
+        b'm' => (&value[..value.len() - 1], ENTITY_UNIT_MILLI),
+        _ => (value, ENTITY_UNIT_NONE),
+    };
+    Some((parse_long(digits)?, unit))
+}
+
+/// Infers the type of an unquoted field value from its suffix, as `LineTcpParser` does.
+fn parse_field_value(value: &[u8]) -> Option<(u8, u8, i64)> {
+    let len = value.len();
+    match value[len - 1] {
+        b'i' if len > 1 && value[1] != b'x' => {
+            parse_long(&value[..len - 1]).map(|v| (ENTITY_TYPE_INTEGER, ENTITY_UNIT_NONE, v))
+        }
+        b'i' if len > 3 && value[0] == b'0' => Some((ENTITY_TYPE_LONG256, ENTITY_UNIT_NONE, 0)),
+        b'n' | b'm' | b't' if len > 1 => {
+            parse_timestamp(value).map(|(v, unit)| (ENTITY_TYPE_TIMESTAMP, unit, v))
+        }
+        b't' | b'T' | b'f' | b'F' if len == 1 => Some((
+            ENTITY_TYPE_BOOLEAN,
+            ENTITY_UNIT_NONE,
+            (value[0] | 32 == b't') as i64,
+        )),
+        b'T' | b'f' | b'F' | b'e' | b'E' => {
+            parse_bool(value).map(|v| (ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, v as i64))
+        }
+        _ => parse_double(value).map(|v| (ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, v.to_bits() as i64)),
+    }
+}
+
+#[derive(Default)]
+pub struct IlpParser {
+    lines: Vec<IlpLine>,
+    entities: Vec<IlpEntity>,
+    strings: Vec<u8>,
+}
+
+impl IlpParser {
+    /// Copies `raw` into the string buffer, dropping escaping backslashes when `escaped`.
+    fn push_str(&mut self, raw: &[u8], escaped: bool) -> StrRef {
+        let offset = self.strings.len() as u32;
+        if escaped {
+            let mut bytes = raw.iter();
+            while let Some(&b) = bytes.next() {
+                let b = if b == b'\\' {
+                    *bytes.next().unwrap_or(&b)
+                } else {
+                    b
+                };
+                self.strings.push(b);
+            }
+        } else {
+            self.strings.extend_from_slice(raw);
+        }
+        StrRef {
+            offset,
+            len: self.strings.len() as u32 - offset,
+        }
+    }
+
+    /// Scans `line` from `pos` up to the first unescaped byte of `stops`, which must include
+    /// the backslash. Returns the end position and whether escapes were seen.
+    fn scan<const N: usize>(line: &[u8], mut pos: usize, stops: [u8; N]) -> (usize, bool) {
+        let mut escaped = false;
+        loop {
+            match find_any(line, pos, stops) {
+                Some(p) if line[p] == b'\\' => {
+                    escaped = true;
+                    pos = p + 2;
+                    if pos >= line.len() {
+                        return (line.len(), true);
+                    }
+                }
+                Some(p) => return (p, escaped),
+                None => return (line.len(), escaped),
+            }
+        }
+    }
+
+    /// Tokenizes a single line without its terminator. Entities of a failed line are
+    /// discarded, the line itself is kept with its error code.
+    fn parse_line(&mut self, line: &[u8]) {
+        let entity_lo = self.entities.len();
+        let mut parsed = IlpLine {
+            measurement: StrRef::default(),
+            entity_lo: entity_lo as u32,
+            entity_count: 0,
+            timestamp: LONG_NULL,
+            error_code: ErrorCode::None,
+            timestamp_unit: ENTITY_UNIT_NONE,
+            _reserved: [0; 3],
+        };
+        match self.tokenize(line, &mut parsed) {
+            Ok(()) => parsed.entity_count = (self.entities.len() - entity_lo) as u32,
+            Err(code) => {
+                self.entities.truncate(entity_lo);
+                parsed.error_code = code;
+            }
+        }
+        self.lines.push(parsed);
+    }
+
+    fn push_entity(
+        &mut self,
+        name: StrRef,
+        value: StrRef,
+        entity_type: u8,
+        unit: u8,
+        long_value: i64,
+    ) {
+        self.entities.push(IlpEntity {
+            name,
+            value,
+            entity_type,
+            unit,
+            _reserved: [0; 6],
+            long_value,
+        });
+    }
+
+    fn tokenize(&mut self, line: &[u8], parsed: &mut IlpLine) -> Result<(), ErrorCode> {
+        let (mut pos, escaped) = Self::scan(line, 0, [b',', b' ', b'\\']);
+        if pos == 0 {
+            return Err(ErrorCode::InvalidTableName);
+        }
+        parsed.measurement = self.push_str(&line[..pos], escaped);
+
+        // tags
+        while line.get(pos) == Some(&b',') {
+            let (key_end, key_escaped) = Self::scan(line, pos + 1, [b'=', b',', b' ', b'\\']);
+            if line.get(key_end) != Some(&b'=') {
+                return Err(ErrorCode::IncompleteTag);
+            }
+            if key_end == pos + 1 {
+                return Err(ErrorCode::InvalidColumnName);
+            }
+            let (value_end, value_escaped) = Self::scan(line, key_end + 1, [b',', b' ', b'\\']);
+            if value_end == key_end + 1 {
+                return Err(ErrorCode::MissingTagValue);
+            }
+            let name = self.push_str(&line[pos + 1..key_end], key_escaped);
+            let value = self.push_str(&line[key_end + 1..value_end], value_escaped);
+            self.push_entity(name, value, ENTITY_TYPE_TAG, ENTITY_UNIT_NONE, 0);
+            pos = value_end;
+        }
+        match line.get(pos) {
+            Some(b' ') => {}
+            // As in LineTcpParser, a line can have tags and no fields.
+            None if self.entities.len() > parsed.entity_lo as usize => return Ok(()),
+            _ => return Err(ErrorCode::NoFields),
+        }
+
+        // fields
+        loop {
+            let (key_end, key_escaped) = Self::scan(line, pos + 1, [b'=', b',', b' ', b'\\']);
+            if line.get(key_end) != Some(&b'=') {
+                return Err(ErrorCode::IncompleteField);
+            }
+            if key_end == pos + 1 {
+                return Err(ErrorCode::InvalidColumnName);
+            }
+            let name = self.push_str(&line[pos + 1..key_end], key_escaped);
+            let value_lo = key_end + 1;
+            if line.get(value_lo) == Some(&b'"') {
+                let (quote, escaped) = Self::scan(line, value_lo + 1, [b'"', b'\\']);
+                if quote == line.len() {
+                    return Err(ErrorCode::InvalidFieldValue);
+                }
+                let value = self.push_str(&line[value_lo + 1..quote], escaped);
+                self.push_entity(name, value, ENTITY_TYPE_STRING, ENTITY_UNIT_NONE, 0);
+                pos = quote + 1;
+                if pos < line.len() && line[pos] != b',' && line[pos] != b' ' {
+                    return Err(ErrorCode::InvalidFieldSeparator);
+                }
+            } else {
+                pos = find_any(line, value_lo, [b',', b' ']).unwrap_or(line.len());
+                if pos == value_lo {
+                    return Err(ErrorCode::MissingFieldValue);
+                }
+                let raw = &line[value_lo..pos];
+                let (entity_type, unit, long_value) =
+                    parse_field_value(raw).ok_or(ErrorCode::InvalidFieldValue)?;
+                let value = match entity_type {
+                    // drop the type or unit suffix
+                    ENTITY_TYPE_INTEGER | ENTITY_TYPE_LONG256 | ENTITY_TYPE_TIMESTAMP => {
+                        self.push_str(&raw[..raw.len() - 1], false)
+                    }
+                    _ => self.push_str(raw, false),
+                };
+                self.push_entity(name, value, entity_type, unit, long_value);
+            }
+            if line.get(pos) != Some(&b',') {
+                break;
+            }
+        }
+
+        // timestamp
+        if pos < line.len() {
+            let ts = &line[pos + 1..];
+            if ts.contains(&b' ') {
+                return Err(ErrorCode::InvalidFieldSeparator);
+            }
+            if !ts.is_empty() {
+                let (timestamp, unit) = parse_timestamp(ts).ok_or(ErrorCode::InvalidTimestamp)?;
+                parsed.timestamp = timestamp;
+                parsed.timestamp_unit = unit;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the terminator of the line starting at `pos`, skipping escaped characters, incl.
+    /// escaped line breaks, and the bytes of quoted field values. As in `LineTcpParser`, a
+    /// quote opens a value when it follows the `=` of a field, that is after the first
+    /// unescaped space.
+    fn line_end(buf: &[u8], pos: usize) -> Option<usize> {
+        let mut p = pos;
+        // end of the last escape, the byte before it cannot be a field's `=`
+        let mut escape_end = pos;
+        while p < buf.len() {
+            let n = find_any(buf, p, [b'\n', b'\r', b'\\', b'"'])?;
+            match buf[n] {
+                b'\\' => {
+                    p = n + 2;
+                    escape_end = p;
+                }
+                b'"' if n > escape_end
+                    && buf[n - 1] == b'='
+                    && Self::scan(buf, pos, [b' ', b'\\']).0 < n =>
+                {
+                    // A raw `\n` ends the line inside the value too.
+                    let (close, _) = Self::scan(buf, n + 1, [b'"', b'\n', b'\\']);
+                    match buf.get(close) {
+                        Some(b'"') => p = close + 1,
+                        Some(_) => return Some(close),
+                        None => return None,
+                    }
+                }
+                b'"' => p = n + 1,
+                _ => return Some(n),
+            }
+        }
+        None
+    }
+
+    /// Parses all complete lines of `buf`; with `at_eof` a trailing line without `\n` is
+    /// parsed too. Returns the number of bytes consumed.
+    pub fn parse(&mut self, buf: &[u8], at_eof: bool) -> usize {
+        let mut pos = 0;
+        while pos < buf.len() {
+            let (line_end, next) = match Self::line_end(buf, pos) {
+                Some(n) => (n, n + 1),
+                None if at_eof => (buf.len(), buf.len()),
+                None => break,
+            };
+            let line = &buf[pos..line_end];
+            if !line.is_empty() {
+                self.parse_line(line);
+            }
+            pos = next;
+        }
+        pos
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.entities.clear();
+        self.strings.clear();
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_create(
+    _env: JNIEnv,
+    _class: JClass,
+) -> *mut IlpParser {
+    catch_panic(|| Box::into_raw(Box::default()))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_destroy(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut IlpParser,
+) {
+    catch_panic(|| {
+        if !parser.is_null() {
+            drop(unsafe { Box::from_raw(parser) });
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_parse(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut IlpParser,
+    lo: *const u8,
+    size: jlong,
+    eof: jboolean,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &mut *parser };
+        parser.parse(unsafe { slice(lo, size) }, eof != 0) as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_clear(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *mut IlpParser,
+) {
+    catch_panic(|| {
+        let parser = unsafe { &mut *parser };
+        parser.clear();
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_lineCount(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const IlpParser,
+) -> jlong {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.lines.len() as jlong
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_lines(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const IlpParser,
+) -> *const IlpLine {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.lines.as_ptr()
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_entities(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const IlpParser,
+) -> *const IlpEntity {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.entities.as_ptr()
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_questdb_cutlass_line_tcp_LineTcpParserNative_strings(
+    _env: JNIEnv,
+    _class: JClass,
+    parser: *const IlpParser,
+) -> *const u8 {
+    catch_panic(|| {
+        let parser = unsafe { &*parser };
+        parser.strings.as_ptr()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> IlpParser {
+        let mut parser = IlpParser::default();
+        assert_eq!(parser.parse(input, true), input.len());
+        parser
+    }
+
+    fn text(parser: &IlpParser, s: StrRef) -> &str {
+        let bytes = &parser.strings[s.offset as usize..(s.offset + s.len) as usize];
+        std::str::from_utf8(bytes).unwrap()
+    }
+
+    /// Parses `t v=<value>` and returns the type, unit and text of the field, or the error.
+    fn field(value: &str) -> Result<(u8, u8, String), ErrorCode> {
+        let parser = parse(format!("t v={value}\n").as_bytes());
+        let line = parser.lines[0];
+        if line.error_code != ErrorCode::None {
+            return Err(line.error_code);
+        }
+        let entity = parser.entities[line.entity_lo as usize];
+        assert_eq!(text(&parser, entity.name), "v");
+        Ok((
+            entity.entity_type,
+            entity.unit,
+            text(&parser, entity.value).into(),
+        ))
+    }
+
+    // Cases of LineTcpParserTest.testGetValueType.
+    #[test]
+    fn field_types() {
+        let ok = |entity_type, unit, value: &str| Ok((entity_type, unit, value.to_string()));
+        let cases = [
+            ("t", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "t")),
+            ("T", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "T")),
+            ("f", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "f")),
+            ("F", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "F")),
+            ("FalSe", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "FalSe")),
+            ("tRuE", ok(ENTITY_TYPE_BOOLEAN, ENTITY_UNIT_NONE, "tRuE")),
+            (
+                "\"0x123a4\"",
+                ok(ENTITY_TYPE_STRING, ENTITY_UNIT_NONE, "0x123a4"),
+            ),
+            (
+                "\"0x123a4 looks \\\" like=long256,\\\n but tis not!\"",
+                ok(
+                    ENTITY_TYPE_STRING,
+                    ENTITY_UNIT_NONE,
+                    "0x123a4 looks \" like=long256,\n but tis not!",
+                ),
+            ),
+            ("0x123i", ok(ENTITY_TYPE_LONG256, ENTITY_UNIT_NONE, "0x123")),
+            ("0x1i", ok(ENTITY_TYPE_LONG256, ENTITY_UNIT_NONE, "0x1")),
+            (
+                "0x123a4i",
+                ok(ENTITY_TYPE_LONG256, ENTITY_UNIT_NONE, "0x123a4"),
+            ),
+            ("123i", ok(ENTITY_TYPE_INTEGER, ENTITY_UNIT_NONE, "123")),
+            ("1i", ok(ENTITY_TYPE_INTEGER, ENTITY_UNIT_NONE, "1")),
+            (
+                "-9223372036854775808i",
+                ok(
+                    ENTITY_TYPE_INTEGER,
+                    ENTITY_UNIT_NONE,
+                    "-9223372036854775808",
+                ),
+            ),
+            (
+                "9223372036854775807i",
+                ok(ENTITY_TYPE_INTEGER, ENTITY_UNIT_NONE, "9223372036854775807"),
+            ),
+            ("42t", ok(ENTITY_TYPE_TIMESTAMP, ENTITY_UNIT_MICRO, "42")),
+            ("-42t", ok(ENTITY_TYPE_TIMESTAMP, ENTITY_UNIT_MICRO, "-42")),
+            ("42n", ok(ENTITY_TYPE_TIMESTAMP, ENTITY_UNIT_NANO, "42")),
+            ("42m", ok(ENTITY_TYPE_TIMESTAMP, ENTITY_UNIT_MILLI, "42")),
+            ("1.45", ok(ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, "1.45")),
+            ("1e-13", ok(ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, "1e-13")),
+            ("1", ok(ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, "1")),
+            ("NaN", ok(ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, "NaN")),
+            (
+                "-Infinity",
+                ok(ENTITY_TYPE_FLOAT, ENTITY_UNIT_NONE, "-Infinity"),
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(field(value), expected, "{value}");
+        }
+        let invalid = [
+            "errt",
+            "\"aaa",
+            "123a4i",
+            "oxi",
+            "xi",
+            "oXi",
+            "0xi",
+            "0Xabci",
+            "foobar_t",
+            "foobar_n",
+            "foobar_m",
+            "123a4",
+            "0x1",
+            "e",
+            "i",
+            "aTTTT",
+            "+1i",
+            "9223372036854775808i",
+            "inf",
+            "nan",
+            "-inf",
+            "1e400",
+            "+42t",
+        ];
+        for value in invalid {
+            assert_eq!(field(value), Err(ErrorCode::InvalidFieldValue), "{value}");
+        }
+    }
+
+    #[test]
+    fn float_bits() {
+        let parser = parse(b"t a=1.5,b=Infinity\n");
+        let values: Vec<f64> = parser
+            .entities
+            .iter()
+            .map(|e| f64::from_bits(e.long_value as u64))
+            .collect();
+        assert_eq!(values, [1.5, f64::INFINITY]);
+    }
+
+    #[test]
+    fn line_ends() {
+        let parser = parse(b"t v=1i\r\nt v=2i\rt v=3i\n\nt v=4i 10n\r");
+        let values: Vec<i64> = parser.entities.iter().map(|e| e.long_value).collect();
+        assert_eq!(values, [1, 2, 3, 4]);
+        assert!(parser.lines.iter().all(|l| l.error_code == ErrorCode::None));
+        assert_eq!(parser.lines[3].timestamp, 10);
+        assert_eq!(parser.lines[3].timestamp_unit, ENTITY_UNIT_NANO);
+
+        // A line is left for the next chunk until its terminator arrives.
+        let mut parser = IlpParser::default();
+        assert_eq!(parser.parse(b"t v=1i\rt v=2", false), 7);
+        assert_eq!(parser.lines.len(), 1);
+    }
+
+    #[test]
+    fn line_ends_in_quoted_values() {
+        let parser = parse(b"t f=\"a\rb\",g=\"c\\\"\r\" 1\rt f=\"x\ny\"\nt v=1i\n");
+        let codes: Vec<ErrorCode> = parser.lines.iter().map(|l| l.error_code).collect();
+        // A raw `\n` ends the second line, leaving `y"` as a line of its own.
+        assert_eq!(
+            codes,
+            [
+                ErrorCode::None,
+                ErrorCode::InvalidFieldValue,
+                ErrorCode::NoFields,
+                ErrorCode::None
+            ]
+        );
+        assert_eq!(text(&parser, parser.entities[0].value), "a\rb");
+        assert_eq!(text(&parser, parser.entities[1].value), "c\"\r");
+        assert_eq!(parser.lines[0].timestamp, 1);
+
+        // Quotes only open values of fields, not of tags or escaped `=`.
+        let parser = parse(b"t,k=\"a\rt v=1i\rt f\\=\"b\rt g=1i\n");
+        let codes: Vec<ErrorCode> = parser.lines.iter().map(|l| l.error_code).collect();
+        assert_eq!(
+            codes,
+            [
+                ErrorCode::None,
+                ErrorCode::None,
+                ErrorCode::IncompleteField,
+                ErrorCode::None
+            ]
+        );
+        assert_eq!(text(&parser, parser.entities[0].value), "\"a");
+
+        // An unclosed quote waits for more input.
+        let mut parser = IlpParser::default();
+        assert_eq!(parser.parse(b"t v=1i\nt f=\"a\rb", false), 7);
+        assert_eq!(parser.parse(b"t f=\"a\rb\"\n", false), 10);
+        assert_eq!(text(&parser, parser.entities[1].value), "a\rb");
+    }
+
+    #[test]
+    fn lines() {
+        let parser = parse(
+            b"m\\ 1,tag=a\\,b f=\"s\" 100\nm,tag=x\nm\nm f=1 2 3\nm f=1 12x\n,t=1 f=1\nm,=x f=1\nm,t= f=1\nm f\n",
+        );
+        let codes: Vec<ErrorCode> = parser.lines.iter().map(|l| l.error_code).collect();
+        assert_eq!(
+            codes,
+            [
+                ErrorCode::None,
+                ErrorCode::None,
+                ErrorCode::NoFields,
+                ErrorCode::InvalidFieldSeparator,
+                ErrorCode::InvalidTimestamp,
+                ErrorCode::InvalidTableName,
+                ErrorCode::InvalidColumnName,
+                ErrorCode::MissingTagValue,
+                ErrorCode::IncompleteField,
+            ]
+        );
+        let line = parser.lines[0];
+        assert_eq!(text(&parser, line.measurement), "m 1");
+        assert_eq!((line.entity_count, line.timestamp), (2, 100));
+        let tag = parser.entities[0];
+        assert_eq!(tag.entity_type, ENTITY_TYPE_TAG);
+        assert_eq!(text(&parser, tag.value), "a,b");
+        // Tags without fields, as LineTcpParser accepts them.
+        assert_eq!(parser.lines[1].entity_count, 1);
+        assert_eq!(parser.lines[1].timestamp, LONG_NULL);
+    }
+}
@@ -0,0 +1,155 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Number parsing over ASCII byte slices. Integer digits are consumed eight at a time with
+//! SWAR, floating point parsing defers to the standard library's Eisel-Lemire parser.
+
+use super::scan::{is_8_digits, parse_8_digits};
+
+/// Parses an unsigned run of decimal digits. Returns `None` on an empty input, a non-digit
+/// byte or overflow.
+pub fn parse_u64(s: &[u8]) -> Option<u64> {
+    if s.is_empty() {
+        return None;
+    }
+    let mut value = 0u64;
+    let mut chunks = s.chunks_exact(8);
+    for chunk in &mut chunks {
+        let w = u64::from_le_bytes(chunk.try_into().unwrap());
+        if !is_8_digits(w) {
+            return None;
+        }
+        value = value
+            .checked_mul(100_000_000)?
+            .checked_add(parse_8_digits(w))?;
+    }
+    for &b in chunks.remainder() {
+        let d = b.wrapping_sub(b'0');
+        if d > 9 {
+            return None;
+        }
+        value = value.checked_mul(10)?.checked_add(d as u64)?;
+    }
+    Some(value)
+}
+
+fn split_sign(s: &[u8]) -> (bool, &[u8]) {
+    match s.first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    }
+}
+
+pub fn parse_i64(s: &[u8]) -> Option<i64> {
+    let (negative, digits) = split_sign(s);
+    let magnitude = parse_u64(digits)?;
+    if negative {
+        if magnitude <= i64::MAX as u64 + 1 {
+            Some((magnitude as i64).wrapping_neg())
+        } else {
+            None
+        }
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
+pub fn parse_i32(s: &[u8]) -> Option<i32> {
+    parse_i64(s).and_then(|v| i32::try_from(v).ok())
+}
+
+pub fn parse_i16(s: &[u8]) -> Option<i16> {
+    parse_i64(s).and_then(|v| i16::try_from(v).ok())
+}
+
+pub fn parse_i8(s: &[u8]) -> Option<i8> {
+    parse_i64(s).and_then(|v| i8::try_from(v).ok())
+}
+
+pub fn parse_f64(s: &[u8]) -> Option<f64> {
+    std::str::from_utf8(s).ok()?.parse().ok()
+}
+
+pub fn parse_f32(s: &[u8]) -> Option<f32> {
+    std::str::from_utf8(s).ok()?.parse().ok()
+}
+
+pub fn parse_bool(s: &[u8]) -> Option<bool> {
+    if s.eq_ignore_ascii_case(b"true") {
+        Some(true)
+    } else if s.eq_ignore_ascii_case(b"false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parses the canonical `8-4-4-4-12` hex form of a UUID into `(lo, hi)` longs.
+pub fn parse_uuid(s: &[u8]) -> Option<(u64, u64)> {
+    if s.len() != 36 || [8, 13, 18, 23].iter().any(|&i| s[i] != b'-') {
+        return None;
+    }
+    let mut value = 0u128;
+    for &b in s.iter().filter(|&&b| b != b'-') {
+        let d = (b as char).to_digit(16)?;
+        value = value << 4 | d as u128;
+    }
+    Some((value as u64, (value >> 64) as u64))
+}
+
+/// Parses `[+-]digits[.digits]` into a long scaled by `10^scale`. Extra fraction digits are
+/// rounded half away from zero. Returns `None` on malformed text or when the value does not
+/// fit, `i64::MIN` being the null sentinel.
+pub fn parse_decimal(s: &[u8], scale: u32) -> Option<i64> {
+    let (negative, s) = split_sign(s);
+    let (int, frac) = match s.iter().position(|&b| b == b'.') {
+        Some(dot) => (&s[..dot], &s[dot + 1..]),
+        None => (s, &s[s.len()..]),
+    };
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+    let (frac, extra) = frac.split_at(frac.len().min(scale as usize));
+    let mut value = 0i128;
+    for &b in int.iter().chain(frac) {
+        let d = b.wrapping_sub(b'0');
+        if d > 9 {
+            return None;
+        }
+        value = value * 10 + d as i128;
+        if value > i64::MAX as i128 {
+            return None;
+        }
+    }
+    if !extra.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    value *= 10i128.pow(scale - frac.len() as u32);
+    if extra.first().is_some_and(|&d| d >= b'5') {
+        value += 1;
+    }
+    let value = if negative { -value } else { value };
+    i64::try_from(value).ok().filter(|&v| v != i64::MIN)
+}
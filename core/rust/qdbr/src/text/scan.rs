@@ -0,0 +1,102 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! Byte scanning primitives shared by the text parsers. On x86-64 they use SSE2, which is
+//! part of the baseline instruction set; elsewhere they fall back to SWAR over `u64` words.
+
+const LO_BITS: u64 = 0x0101_0101_0101_0101;
+#[cfg(not(target_arch = "x86_64"))]
+const HI_BITS: u64 = 0x8080_8080_8080_8080;
+
+#[inline]
+fn broadcast(b: u8) -> u64 {
+    LO_BITS * b as u64
+}
+
+/// Flags the high bit of every zero byte of `w`. Bytes above the lowest flagged byte may be
+/// false positives, so only the lowest flag is exact.
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+fn zero_bytes(w: u64) -> u64 {
+    w.wrapping_sub(LO_BITS) & !w & HI_BITS
+}
+
+/// Returns the index of the first byte at or after `from` equal to `a`.
+pub fn find_byte(buf: &[u8], from: usize, a: u8) -> Option<usize> {
+    find_any(buf, from, [a])
+}
+
+/// Returns the index of the first byte at or after `from` equal to any byte of `set`.
+pub fn find_any<const N: usize>(buf: &[u8], from: usize, set: [u8; N]) -> Option<usize> {
+    let mut i = from;
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::*;
+        // SAFETY: SSE2 is always available on x86-64 and loads stay within `buf`.
+        unsafe {
+            let needles = set.map(|b| _mm_set1_epi8(b as i8));
+            while i + 16 <= buf.len() {
+                let v = _mm_loadu_si128(buf.as_ptr().add(i) as *const __m128i);
+                let eq = needles.iter().fold(_mm_setzero_si128(), |acc, &n| {
+                    _mm_or_si128(acc, _mm_cmpeq_epi8(v, n))
+                });
+                let mask = _mm_movemask_epi8(eq) as u32;
+                if mask != 0 {
+                    return Some(i + mask.trailing_zeros() as usize);
+                }
+                i += 16;
+            }
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let needles = set.map(broadcast);
+        while i + 8 <= buf.len() {
+            let w = u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+            let hits = needles.iter().fold(0, |acc, &n| acc | zero_bytes(w ^ n));
+            if hits != 0 {
+                return Some(i + (hits.trailing_zeros() / 8) as usize);
+            }
+            i += 8;
+        }
+    }
+    buf[i..].iter().position(|b| set.contains(b)).map(|p| i + p)
+}
+
+/// Returns true when all bytes of the 8-byte little-endian word are ASCII digits.
+#[inline]
+pub fn is_8_digits(w: u64) -> bool {
+    // Adding 6 pushes digits 0-9 to 0x36-0x3F and anything above '9' into the next nibble.
+    (w & 0xF0F0_F0F0_F0F0_F0F0) == broadcast(b'0')
+        && (w.wrapping_add(broadcast(6)) & 0xF0F0_F0F0_F0F0_F0F0) == broadcast(b'0')
+}
+
+/// Converts 8 ASCII digits, most significant first in memory, into their value.
+#[inline]
+pub fn parse_8_digits(w: u64) -> u64 {
+    let w = w - broadcast(b'0');
+    let w = (w.wrapping_mul(10) + (w >> 8)) & 0x00FF_00FF_00FF_00FF;
+    let w = (w.wrapping_mul(100) + (w >> 16)) & 0x0000_FFFF_0000_FFFF;
+    (w.wrapping_mul(10000) + (w >> 32)) & 0xFFFF_FFFF
+}
@@ -0,0 +1,158 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+//! ISO-8601 timestamp parsing into and formatting from epoch microseconds.
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // H. Hinnant's algorithm, with years starting in March.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian `(year, month, day)` of a day since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Parses exactly `n` ASCII digits at `s[at..]`.
+fn digits(s: &[u8], at: usize, n: usize) -> Option<u32> {
+    let field = s.get(at..at + n)?;
+    field.iter().try_fold(0u32, |acc, &b| {
+        let d = b.wrapping_sub(b'0');
+        (d <= 9).then_some(acc * 10 + d as u32)
+    })
+}
+
+/// Parses `YYYY-MM-DD[(T| )HH:MM[:SS[.fffffffff]]][Z|(+|-)HH[[:]MM]]` into epoch micros.
+/// Fractions finer than a microsecond are truncated.
+pub fn parse_timestamp(s: &[u8]) -> Option<i64> {
+    let year = digits(s, 0, 4)? as i64;
+    let month = digits(s, 5, 2)?;
+    let day = digits(s, 8, 2)?;
+    if s.get(4) != Some(&b'-') || s.get(7) != Some(&b'-') {
+        return None;
+    }
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    let mut micros = days_from_civil(year, month, day) * MICROS_PER_DAY;
+    let mut pos = 10;
+    if pos == s.len() {
+        return Some(micros);
+    }
+
+    if s[pos] != b'T' && s[pos] != b' ' {
+        return None;
+    }
+    let hour = digits(s, pos + 1, 2)?;
+    let minute = digits(s, pos + 4, 2)?;
+    if s.get(pos + 3) != Some(&b':') || hour > 23 || minute > 59 {
+        return None;
+    }
+    let mut seconds = (hour * 3600 + minute * 60) as i64;
+    pos += 6;
+    if s.get(pos) == Some(&b':') {
+        let second = digits(s, pos + 1, 2)?;
+        if second > 59 {
+            return None;
+        }
+        seconds += second as i64;
+        pos += 3;
+        if s.get(pos) == Some(&b'.') {
+            pos += 1;
+            let start = pos;
+            let mut fraction = 0i64;
+            while let Some(d) = s.get(pos).map(|b| b.wrapping_sub(b'0')).filter(|&d| d <= 9) {
+                if pos - start < 6 {
+                    fraction = fraction * 10 + d as i64;
+                }
+                pos += 1;
+            }
+            let n = pos - start;
+            if n == 0 || n > 9 {
+                return None;
+            }
+            for _ in n..6 {
+                fraction *= 10;
+            }
+            micros += fraction;
+        }
+    }
+    micros += seconds * MICROS_PER_SECOND;
+
+    match s.get(pos) {
+        None => Some(micros),
+        Some(b'Z') if pos + 1 == s.len() => Some(micros),
+        Some(&sign @ (b'+' | b'-')) => {
+            let tz_hour = digits(s, pos + 1, 2)?;
+            let tz_minute = match s.len() - pos {
+                3 => 0,
+                5 => digits(s, pos + 3, 2)?,
+                6 if s[pos + 3] == b':' => digits(s, pos + 4, 2)?,
+                _ => return None,
+            };
+            if tz_hour > 23 || tz_minute > 59 {
+                return None;
+            }
+            let offset = (tz_hour * 3600 + tz_minute * 60) as i64 * MICROS_PER_SECOND;
+            Some(if sign == b'+' {
+                micros - offset
+            } else {
+                micros + offset
+            })
+        }
+        _ => None,
+    }
+}
@@ -32,6 +32,7 @@ import io.questdb.jit.JitUtil;
 import io.questdb.log.Log;
 import io.questdb.log.LogFactory;
 import io.questdb.log.LogRecord;
+import io.questdb.log.RustLog;
 import io.questdb.network.IODispatcherConfiguration;
 import io.questdb.network.Net;
 import io.questdb.std.*;
@@ -112,6 +113,7 @@ public class Bootstrap {
             LogFactory.configureRootDir(rootDirectory);
         }
         log = LogFactory.getLog(LOG_NAME);
+        RustLog.init(RustLog.LEVEL_INFO);
 
         // report copyright and architecture
         log.advisoryW()
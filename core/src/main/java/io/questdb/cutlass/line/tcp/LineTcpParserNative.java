@@ -0,0 +1,73 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+
+package io.questdb.cutlass.line.tcp;
+
+/**
+ * Native ILP parser, implemented in the Rust library (libquestdbr).
+ * <p>
+ * parse() tokenizes complete lines of the input into a table of lines and a table of
+ * entities (tags and fields), and returns the number of bytes consumed. The unconsumed tail
+ * has to be passed again with the next chunk of input. Names and values are unescaped into
+ * a string buffer and referenced by (offset, length) pairs of ints.
+ * <p>
+ * Entity types, timestamp units and error codes use the values of {@link LineTcpParser}, error
+ * codes being {@link LineTcpParser.ErrorCode} ordinals. Entities of lines with errors are dropped.
+ */
+public final class LineTcpParserNative {
+    public static final int ENTITY_LONG_VALUE_OFFSET = 24;
+    public static final int ENTITY_NAME_OFFSET = 0;
+    public static final int ENTITY_SIZE = 32;
+    public static final int ENTITY_TYPE_OFFSET = 16;
+    public static final int ENTITY_UNIT_OFFSET = 17;
+    public static final int ENTITY_VALUE_OFFSET = 8;
+    public static final int LINE_ENTITY_COUNT_OFFSET = 12;
+    public static final int LINE_ENTITY_LO_OFFSET = 8;
+    public static final int LINE_ERROR_CODE_OFFSET = 24;
+    public static final int LINE_MEASUREMENT_OFFSET = 0;
+    public static final int LINE_SIZE = 32;
+    public static final int LINE_TIMESTAMP_OFFSET = 16;
+    public static final int LINE_TIMESTAMP_UNIT_OFFSET = 28;
+
+    private LineTcpParserNative() {
+    }
+
+    public static native void clear(long parser);
+
+    public static native long create();
+
+    public static native void destroy(long parser);
+
+    public static native long entities(long parser);
+
+    public static native long lineCount(long parser);
+
+    public static native long lines(long parser);
+
+    // returns number of bytes consumed, with eof a trailing line without '\n' or '\r' is parsed too
+    public static native long parse(long parser, long lo, long size, boolean eof);
+
+    public static native long strings(long parser);
+}
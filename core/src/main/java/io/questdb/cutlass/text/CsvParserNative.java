@@ -0,0 +1,74 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+
+package io.questdb.cutlass.text;
+
+/**
+ * Native CSV parser for COPY, implemented in the Rust library (libquestdbr).
+ * <p>
+ * The parser is created for a list of column types (ColumnType tags, UNDEFINED skips the column)
+ * and fed with chunks of the input file. Complete rows are appended to native column buffers,
+ * an incomplete trailing row is carried over to the next chunk. After each batch the buffers
+ * are read via column*() methods and reset with clear().
+ * <p>
+ * Fixed-size columns use the storage layout and null sentinels. VARCHAR and SYMBOL columns hold UTF-8
+ * bytes in the data buffer and a 16-byte (offset, length) aux entry per row, length is -1 for null;
+ * SYMBOL values are left to be looked up in the symbol table. STRING columns use the storage layout,
+ * an int length (-1 for null) and UTF-16 chars in the data buffer and a long data offset per row in
+ * the aux buffer. Blank lines are skipped, also before the header.
+ * rowErrors() points to one byte per row, set when the row had unparsable values or a wrong
+ * number of fields.
+ */
+public final class CsvParserNative {
+
+    private CsvParserNative() {
+    }
+
+    public static native void clear(long parser);
+
+    // The column getters return 0 for addresses and -1 for sizes and counts when columnIndex is out of range
+
+    // returns 0 for columns other than VARCHAR, SYMBOL and STRING
+    public static native long columnAux(long parser, int columnIndex);
+
+    public static native long columnData(long parser, int columnIndex);
+
+    public static native long columnDataSize(long parser, int columnIndex);
+
+    public static native long columnErrors(long parser, int columnIndex);
+
+    // returns 0 when any of the column types is not supported
+    public static native long create(byte delimiter, byte quote, boolean skipHeader, long pColumnTypes, int columnCount);
+
+    public static native void destroy(long parser);
+
+    // parses the row left at the end of input, returns row count of the batch
+    public static native long finish(long parser);
+
+    // returns row count of the batch
+    public static native long parse(long parser, long lo, long size);
+
+    public static native long rowErrors(long parser);
+}
@@ -0,0 +1,70 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+package io.questdb.cutlass.text;
+
+import io.questdb.std.ColumnDesc;
+
+/**
+ * Native export encoders, implemented in the Rust library (libquestdbr).
+ * <p>
+ * Columns are described by an array of native column descriptors, see {@link ColumnDesc}.
+ * Encoders append rows [rowLo, rowHi) to a native buffer, which is drained via
+ * bufferAddress()/bufferSize() and reset with clearBuffer().
+ * <p>
+ * Encoders return the buffer size, or a negative ColumnDesc.ERROR_* code. On error the buffer
+ * is left as it was before the call.
+ */
+public final class ExportNative {
+
+    private ExportNative() {
+    }
+
+    public static native long bufferAddress(long buffer);
+
+    public static native long bufferSize(long buffer);
+
+    public static native void clearBuffer(long buffer);
+
+    public static native long createBuffer();
+
+    public static native void destroyBuffer(long buffer);
+
+    // RFC 4180 CSV lines ending with CRLF, fields are quoted only when they contain the delimiter, a quote or a line
+    // break, nulls are empty fields. The delimiter must not be a quote or a line break
+    public static native long writeCsv(long buffer, long pColumns, int columnCount, long rowLo, long rowHi, byte delimiter);
+
+    // the line of column names, quoted as in writeCsv()
+    public static native long writeCsvHeader(long buffer, long pColumns, int columnCount, byte delimiter);
+
+    // one JSON object per row, keyed by column name
+    public static native long writeNdjson(long buffer, long pColumns, int columnCount, long rowLo, long rowHi);
+
+    // PostgreSQL binary COPY tuples, types are mapped as PG wire sends them in binary: BYTE and SHORT as int2,
+    // DATE and TIMESTAMP as timestamp, UUID as uuid; CHAR, STRING, SYMBOL, VARCHAR, IPv4, LONG256 and geohashes as text
+    public static native long writePgCopy(long buffer, long pColumns, int columnCount, long rowLo, long rowHi);
+
+    public static native long writePgCopyHeader(long buffer);
+
+    public static native long writePgCopyTrailer(long buffer);
+}
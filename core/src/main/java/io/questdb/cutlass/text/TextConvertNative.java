@@ -0,0 +1,112 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+
+package io.questdb.cutlass.text;
+
+/**
+ * Batch conversions between text and column values, implemented in the Rust library (libquestdbr).
+ * <p>
+ * Text uses the layout of CsvParserNative VARCHAR columns: UTF-8 bytes in a data buffer and a 16-byte
+ * (offset, length) aux entry per value, length is -1 for null. Parsers write the null sentinel for nulls
+ * and unparsable values and return the number of unparsable values. Formatters write nulls as null
+ * entries and return the data size. When it exceeds dataCapacity, writing stops at the first value that
+ * does not fit, so the data and aux entries written before it are left behind, and the call has to be
+ * repeated with at least that capacity. Numbers take up to FORMAT_MAX_SIZE bytes per value.
+ * <p>
+ * BINARY encoders take the column aux (a long data offset per value) and data (a long length, -1 for null,
+ * followed by the bytes). They check dataCapacity as formatters do, and return -1 when an offset or length is
+ * out of bounds, leaving the entries before that value behind. 2 * dataSize bytes of data capacity always
+ * suffice.
+ * <p>
+ * Decimals are longs scaled by 10^scale, scale being 0 to MAX_DECIMAL_SCALE, with Numbers.LONG_NULL for null,
+ * so their range is +-Long.MAX_VALUE. Their conversions round half away from zero and return -1 on invalid scale.
+ * <p>
+ * GeoHash conversions match GeoHashes. Hash buffers use the storage width of the precision (1 to 60 bits):
+ * byte, short, int or long, with -1 for null. They return -1 on invalid precision.
+ */
+public final class TextConvertNative {
+    public static final int AUX_ENTRY_SIZE = 16;
+    public static final int FORMAT_MAX_SIZE = 32;
+    public static final int MAX_DECIMAL_SCALE = 18;
+    public static final int UUID_TEXT_SIZE = 36;
+
+    private TextConvertNative() {
+    }
+
+    // writes NaN for nulls
+    public static native long decimalToDouble(long pValues, long count, int scale, long pOut);
+
+    // writes the center of each cell, NaN for nulls
+    public static native long decodeGeoHash(long pHashes, long count, int bits, long pLatOut, long pLonOut);
+
+    // rounds the shortest decimal text of each value as BigDecimal.valueOf() does, so 1.005 is 1.01 at scale 2;
+    // returns the number of values out of range, written as null along with NaNs
+    public static native long doubleToDecimal(long pValues, long count, int scale, long pOut);
+
+    // RFC 4648 base64 with padding
+    public static native long encodeBase64(long pData, long dataSize, long pAux, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // returns the number of out of range coordinates, written as null
+    public static native long encodeGeoHash(long pLat, long pLon, long count, int bits, long pHashesOut);
+
+    // lowercase, two digits per byte
+    public static native long encodeHex(long pData, long dataSize, long pAux, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // exactly scale fraction digits
+    public static native long formatDecimal(long pValues, long count, int scale, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // NaN and infinities are written as null
+    public static native long formatDouble(long pValues, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // NaN and infinities are written as null
+    public static native long formatFloat(long pValues, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // base32 when bits is a multiple of 5, otherwise a binary string; bits bytes per value at most
+    public static native long formatGeoHash(long pHashes, long count, int bits, long pDataOut, long dataCapacity, long pAuxOut);
+
+    public static native long formatInt(long pValues, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    public static native long formatLong(long pValues, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // lowercase canonical form, UUID_TEXT_SIZE bytes per value
+    public static native long formatUuid(long pValues, long count, long pDataOut, long dataCapacity, long pAuxOut);
+
+    // [+-]digits[.digits], extra fraction digits are rounded
+    public static native long parseDecimal(long pData, long dataSize, long pAux, long count, int scale, long pOut);
+
+    public static native long parseDouble(long pData, long dataSize, long pAux, long count, long pOut);
+
+    public static native long parseFloat(long pData, long dataSize, long pAux, long count, long pOut);
+
+    // base32 text of at least bits precision is truncated to bits, characters past the 12th are ignored
+    public static native long parseGeoHash(long pData, long dataSize, long pAux, long count, int bits, long pHashesOut);
+
+    public static native long parseInt(long pData, long dataSize, long pAux, long count, long pOut);
+
+    public static native long parseLong(long pData, long dataSize, long pAux, long count, long pOut);
+
+    // canonical 8-4-4-4-12 hex form, values are (lo, hi) long pairs as in UUID columns
+    public static native long parseUuid(long pData, long dataSize, long pAux, long count, long pOut);
+}
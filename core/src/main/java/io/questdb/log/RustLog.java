@@ -0,0 +1,86 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+package io.questdb.log;
+
+import io.questdb.std.Os;
+
+/**
+ * Receives log records of the Rust library (libquestdbr) and writes them to the "rust" log.
+ * Levels follow the Rust log crate: ERROR and WARN are logged as errors, INFO as info, DEBUG
+ * and TRACE as debug. The record target, typically the Rust module path, prefixes the message.
+ */
+public final class RustLog {
+    public static final int LEVEL_DEBUG = 4;
+    public static final int LEVEL_ERROR = 1;
+    public static final int LEVEL_INFO = 3;
+    public static final int LEVEL_OFF = 0;
+    public static final int LEVEL_TRACE = 5;
+    public static final int LEVEL_WARN = 2;
+    private static final Log LOG = LogFactory.getLog("rust");
+
+    private RustLog() {
+    }
+
+    // Registers this class as the sink of native log records, records above maxLevel are dropped natively.
+    // Returns false when the Rust library is not loaded, or was built before native logging was added, in which
+    // case native records are not logged.
+    public static boolean init(int maxLevel) {
+        if (Os.type == Os._32Bit) {
+            return false;
+        }
+        try {
+            if (init0(maxLevel)) {
+                return true;
+            }
+            LOG.error().$("could not register native logger").$();
+        } catch (UnsatisfiedLinkError e) {
+            LOG.advisory().$("native logger is not available, the Rust library is outdated [error=").$(e.getMessage()).I$();
+        }
+        return false;
+    }
+
+    // Logs message natively at the given level, for tests checking that native records reach the "rust" log
+    public static native void testLog(int level, String message);
+
+    // called from native code
+    @SuppressWarnings("unused")
+    private static void log(int level, String target, String message) {
+        final LogRecord record;
+        switch (level) {
+            case LEVEL_ERROR:
+            case LEVEL_WARN:
+                record = LOG.error();
+                break;
+            case LEVEL_INFO:
+                record = LOG.info();
+                break;
+            default:
+                record = LOG.debug();
+                break;
+        }
+        record.$('[').$(target).$("] ").$(message).$();
+    }
+
+    private static native boolean init0(int maxLevel);
+}
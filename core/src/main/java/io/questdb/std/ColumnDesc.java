@@ -0,0 +1,55 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+
+package io.questdb.std;
+
+/**
+ * Layout of the native column descriptor read by the Rust library (libquestdbr), see
+ * ExportNative and Kernels. A descriptor points at the column files of a partition or the
+ * buffers of a page frame: data and aux (STRING offsets, VARCHAR aux entries) with their
+ * sizes, and for SYMBOL columns the key offsets of the .o file (past its header) and the .c file.
+ * <p>
+ * Natives taking descriptors return the negative ERROR_* codes when a column can't be read.
+ */
+public final class ColumnDesc {
+    public static final int AUX_OFFSET = 32;
+    public static final int AUX_SIZE_OFFSET = 40;
+    public static final int DATA_OFFSET = 16;
+    public static final int DATA_SIZE_OFFSET = 24;
+    // a column is shorter than the rows requested or its data is inconsistent
+    public static final int ERROR_CORRUPT_COLUMN = -2;
+    public static final int ERROR_UNSUPPORTED_TYPE = -1;
+    public static final int NAME_OFFSET = 8;
+    public static final int NAME_SIZE_OFFSET = 4;
+    public static final int SIZE = 80;
+    public static final int SYMBOL_CHARS_OFFSET = 64;
+    public static final int SYMBOL_CHARS_SIZE_OFFSET = 72;
+    public static final int SYMBOL_COUNT_OFFSET = 56;
+    public static final int SYMBOL_OFFSETS_OFFSET = 48;
+    public static final int TYPE_OFFSET = 0;
+
+    private ColumnDesc() {
+    }
+}
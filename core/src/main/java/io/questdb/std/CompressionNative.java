@@ -0,0 +1,93 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+package io.questdb.std;
+
+/**
+ * Block compression for WAL segments and cold column files, implemented in the Rust library
+ * (libquestdbr).
+ * <p>
+ * The compressor splits its input into blocks of a fixed size, each written as an independent
+ * zstd frame with a content checksum, and finish() appends a seek table in the zstd seekable
+ * format. Compressed output accumulates in a native buffer, read via outAddress() and the size
+ * returned by compress()/finish(), and reset with clearOut(). After finish(), compress() and
+ * finish() fail until reset() starts the next stream.
+ * <p>
+ * A reader is created over a complete compressed file, typically memory mapped, which must
+ * stay mapped for the lifetime of the reader. Reads decode only the blocks they overlap.
+ * <p>
+ * compressBuffer()/decompressBuffer() are one-shot codecs for buffers that do not need seeking,
+ * e.g. network send buffers and temporary files. The codec is selected by its Parquet
+ * CompressionCodec id, the level is a zstd level or 0-9 for GZIP, and is ignored by SNAPPY and
+ * LZ4_RAW.
+ */
+public final class CompressionNative {
+    public static final int CODEC_GZIP = 2;
+    // LZ4 block format without framing
+    public static final int CODEC_LZ4_RAW = 7;
+    public static final int CODEC_SNAPPY = 1;
+    public static final int CODEC_ZSTD = 6;
+    // upper bound of createCompressor() blockSize, readers reject seek tables with larger frames
+    public static final int MAX_BLOCK_SIZE = 64 * 1024 * 1024;
+
+    private CompressionNative() {
+    }
+
+    public static native void clearOut(long compressor);
+
+    // returns compressed output size, or -1 on failure or after finish()
+    public static native long compress(long compressor, long lo, long size);
+
+    // returns compressed size, or -1 for an unknown codec or level, or when dstCapacity is too small
+    public static native long compressBuffer(int codec, int level, long src, long srcSize, long dst, long dstCapacity);
+
+    // worst case compressed size for compressBuffer(), or -1 for an unknown codec
+    public static native long compressBufferBound(int codec, long srcSize);
+
+    // returns 0 when the level is not supported or blockSize is not in (0, MAX_BLOCK_SIZE]
+    public static native long createCompressor(int level, int blockSize);
+
+    // returns 0 when the buffer does not end with a seek table, or the table has a frame larger than MAX_BLOCK_SIZE
+    public static native long createReader(long lo, long size);
+
+    // returns decompressed size, or -1 for an unknown codec, when the input is corrupt or dstCapacity is too small
+    public static native long decompressBuffer(int codec, long src, long srcSize, long dst, long dstCapacity);
+
+    public static native long decompressedSize(long reader);
+
+    public static native void destroyCompressor(long compressor);
+
+    public static native void destroyReader(long reader);
+
+    // flushes the last block and appends the seek table, returns compressed output size, or -1 on failure or when
+    // already finished
+    public static native long finish(long compressor);
+
+    public static native long outAddress(long compressor);
+
+    // returns number of bytes read, 0 past the end, or -1 when a block is corrupt
+    public static native long read(long reader, long offset, long dst, long size);
+
+    // drops the output and any unfinished stream, and starts the next stream
+    public static native void reset(long compressor);
+}
@@ -0,0 +1,195 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+
+package io.questdb.std;
+
+/**
+ * Vectorised kernels implemented in the Rust library (libquestdbr).
+ * <p>
+ * Row-selection bitmaps hold one bit per row, packed into (rowCount + 63) / 64 longs. Methods returning long return
+ * Os.RUST_PANIC when the Rust code panics, see Os.checkRustResult().
+ */
+public final class Kernels {
+    // Layout of the partial aggregates written by aggregateLong() and aggregateDouble():
+    // count (long), sum, min, max (long or double). Min and max are null when count is 0.
+    public static final int AGGREGATES_COUNT_OFFSET = 0;
+    public static final int AGGREGATES_MAX_OFFSET = 24;
+    public static final int AGGREGATES_MIN_OFFSET = 16;
+    public static final int AGGREGATES_SIZE = 32;
+    public static final int AGGREGATES_SUM_OFFSET = 8;
+    // Pattern kinds of createMatcher()
+    public static final int MATCH_ILIKE = 1;
+    public static final int MATCH_LIKE = 0;
+    public static final int MATCH_REGEX = 2;
+    // Operators of compare*(). BETWEEN is inclusive and accepts bounds in either order.
+    public static final int OP_BETWEEN = 6;
+    public static final int OP_EQ = 0;
+    public static final int OP_GE = 5;
+    public static final int OP_GT = 4;
+    public static final int OP_LE = 3;
+    public static final int OP_LT = 2;
+    public static final int OP_NE = 1;
+    // Stride units of sampleByBuckets()
+    public static final int SAMPLE_BY_UNIT_MICROS = 0;
+    public static final int SAMPLE_BY_UNIT_MONTHS = 1;
+    public static final int SAMPLE_BY_UNIT_YEARS = 2;
+    // Layout of the group-by-symbol table entries: key (long), row count (long), then aggregates.
+    public static final int SYMBOL_AGGREGATES_AGGREGATES_OFFSET = 16;
+    public static final int SYMBOL_AGGREGATES_KEY_OFFSET = 0;
+    public static final int SYMBOL_AGGREGATES_ROWS_OFFSET = 8;
+    public static final int SYMBOL_AGGREGATES_SIZE = SYMBOL_AGGREGATES_AGGREGATES_OFFSET + AGGREGATES_SIZE;
+
+    private Kernels() {
+    }
+
+    // Writes the partial aggregates to pAggregatesOut, returns 0. NaN and infinities are skipped, as sum(double) and
+    // count(double) skip values failing Numbers.isFinite()
+    public static native long aggregateDouble(long pValues, long rowCount, long pAggregatesOut);
+
+    // Writes the partial aggregates to pAggregatesOut, returns 0
+    public static native long aggregateLong(long pValues, long rowCount, long pAggregatesOut);
+
+    // Writes for each probe timestamp the index of the latest row at or before it to pRowsOut (probeCount longs),
+    // -1 when there is none. Timestamps must be ascending, ascending probes are searched incrementally. Returns 0
+    public static native long asofRows(long pTimestamps, long rowCount, long pProbes, long probeCount, long pRowsOut);
+
+    // pRowsOut must have capacity for rowCount longs
+    public static native long bitmapToRows(long pBitmap, long rowCount, long pRowsOut);
+
+    // Selects rows matching "value op a" (a <= value <= b for BETWEEN) into pBitmapOut as the SQL double operators do,
+    // returns selected row count, or -1 on unknown operator. = is Numbers.equals(), so values within DOUBLE_TOLERANCE
+    // are equal and NaN and infinities are all null and equal to each other. < is !equals && value < a, <= is
+    // equals || value < a, likewise > and >=. BETWEEN is >= lower and <= upper bound, a null bound selects nothing
+    public static native long compareDouble(long pValues, long rowCount, int op, double a, double b, long pBitmapOut);
+
+    // See compareLong(), null is Numbers.INT_NULL
+    public static native long compareInt(long pValues, long rowCount, int op, int a, int b, long pBitmapOut);
+
+    // Selects rows matching "value op a" (a <= value <= b for BETWEEN) into pBitmapOut, returns selected row count,
+    // or -1 on unknown operator. Nulls never match ordering operators or BETWEEN, match !=, and "= null" selects nulls.
+    // Null is Numbers.LONG_NULL; also used for TIMESTAMP and DATE columns
+    public static native long compareLong(long pValues, long rowCount, int op, long a, long b, long pBitmapOut);
+
+    // LIKE and ILIKE patterns must match the whole value, with % and _ wildcards and \ escapes as in SQL LIKE.
+    // ILIKE folds ASCII letters only, as Pattern.CASE_INSENSITIVE does, and returns 0 for non-ASCII patterns.
+    // REGEX patterns match anywhere in the value, as ~ does. Patterns are UTF-8, returns 0 on malformed pattern.
+    // REGEX patterns have java.util.regex semantics, 0 is also returned for syntax the native engine cannot match
+    // the same way (e.g. lookaround, backreferences, possessive quantifiers, inline flags, \b and \p{..} classes),
+    // the caller should then match with java.util.regex
+    public static native long createMatcher(long pPattern, long patternSize, int kind);
+
+    // A panic here is only reported by Os.rustLastPanic()
+    public static native void destroyMatcher(long matcher);
+
+    // Writes indexes of the rows to keep to pRowsOut (capacity rowCount longs), the last row of each
+    // (timestamp, keys) group wins. Timestamps must be ascending. Key columns are described by
+    // ColumnDesc descriptors; returns row count, or a negative ColumnDesc.ERROR_* code
+    public static native long dedupRows(long pTimestamps, long rowCount, long pKeyColumns, int keyColumnCount, long pRowsOut);
+
+    // Writes the [lo, hi) row range of the ascending timestamp buffer matching the interval to pRangeOut (2 longs).
+    // tsLo and tsHi are inclusive. Returns 0
+    public static native long findRowsForInterval(long pTimestamps, long rowCount, long tsLo, long tsHi, long pRangeOut);
+
+    // pTableOut must have capacity for symbolCount + 1 entries, returns entry count or -1 on out of range key or
+    // negative count.
+    // Values are aggregated as by aggregateDouble()
+    public static native long groupBySymbolDouble(long pKeys, long pValues, long rowCount, int symbolCount, long pTableOut);
+
+    // pTableOut must have capacity for symbolCount + 1 entries, returns entry count or -1 on out of range key or
+    // negative count
+    public static native long groupBySymbolLong(long pKeys, long pValues, long rowCount, int symbolCount, long pTableOut);
+
+    // Writes 64-bit hashes of the key columns of rows [rowLo, rowHi) to pHashesOut (rowHi - rowLo longs). A single
+    // key column hashes as the matching Hash.hash*64() function, nulls hash as their sentinel values (empty value
+    // for STRING and VARCHAR). Returns 0, or a negative ColumnDesc.ERROR_* code
+    public static native long hashRows(long pKeyColumns, int keyColumnCount, long rowLo, long rowHi, long pHashesOut);
+
+    // Writes indexes of STRING or VARCHAR rows in [rowLo, rowHi) matching the pattern to pRowsOut (capacity
+    // rowHi - rowLo longs), nulls never match. The column is described by a ColumnDesc descriptor;
+    // returns row count, or a negative ColumnDesc.ERROR_* code, ERROR_CORRUPT_COLUMN also on a bad row range or
+    // a 0 matcher
+    public static native long matchRows(long matcher, long pColumn, long rowLo, long rowHi, long pRowsOut);
+
+    // Gathers values of valueSize bytes (1, 2, 4, 8, 16 or 32) from the existing column and the O3 batch in merge
+    // index order. Returns 0, or -1 on a negative count, a null buffer, unsupported value size or out of range
+    // index row
+    public static native long mergeShuffle(
+            long pColumn,
+            long columnRowCount,
+            long pO3,
+            long o3RowCount,
+            long pIndex,
+            long indexCount,
+            long pDest,
+            int valueSize
+    );
+
+    // Merges timestamps [tsLo, tsLo + tsCount) with the sorted O3 index into pIndexOut (tsCount + o3Count entries).
+    // Uses the Vect merge index layout: (ts, row) pairs, row has the top bit set when it is a column row. On equal
+    // timestamps column rows go first, as in Vect.mergeTwoLongIndexesAsc(). Returns the number of entries written, or
+    // -1 on a negative count or a null buffer
+    public static native long mergeTimestampIndex(
+            long pTimestamps,
+            long tsLo,
+            long tsCount,
+            long pO3Index,
+            long o3Count,
+            long pIndexOut
+    );
+
+    // Assigns rows of the ascending timestamp buffer to SAMPLE BY buckets, as the TimestampSampler of the unit started
+    // at origin would. Writes the bucket index of each row to pBucketsOut (rowCount longs) and the [lo, hi) bounds of
+    // each bucket to pBoundsOut (2 * rowCount longs), empty buckets are skipped. Month and year buckets start on the day
+    // and time of the origin, clamped to the month end as MonthTimestampSampler and YearTimestampSampler clamp them. Like
+    // YearTimestampSampler.round(), year buckets over 1 year take the month lengths of the row's year. Returns bucket
+    // count, or -1 on unknown unit or non-positive stride
+    public static native long sampleByBuckets(
+            long pTimestamps,
+            long rowCount,
+            long origin,
+            long stride,
+            int unit,
+            long pBucketsOut,
+            long pBoundsOut
+    );
+
+    // tsLo and tsHi are inclusive
+    public static native long selectInterval(long pTimestamps, long rowCount, long tsLo, long tsHi, long pBitmapOut);
+
+    // tsLo and tsHi are inclusive
+    public static native long selectIntervalSymbol(
+            long pTimestamps,
+            long pKeys,
+            int key,
+            long rowCount,
+            long tsLo,
+            long tsHi,
+            long pBitmapOut
+    );
+
+    // Writes up to n row indexes with the latest (or earliest when last is false) timestamps, newest first
+    // (or oldest first). pBitmap is an optional row-selection bitmap, 0 selects all rows.
+    public static native long topNRows(long pTimestamps, long rowCount, long pBitmap, long n, boolean last, long pRowsOut);
+}
@@ -25,6 +25,7 @@
 package io.questdb.std;
 
 import com.sun.management.OperatingSystemMXBean;
+import io.questdb.cairo.CairoException;
 import io.questdb.std.ex.FatalError;
 import io.questdb.std.ex.KerberosException;
 import io.questdb.std.str.Path;
@@ -44,6 +45,14 @@ public final class Os {
     public static final int FREEBSD = 4;
     public static final int LINUX = 2;
     public static final long PARK_NANOS_MAX = 5 * 1_000_000_000L;
+    // Rust library allocator statistics, see rustAllocatorStat()
+    public static final int RUST_ALLOCATOR_STAT_ACTIVE = 1;
+    public static final int RUST_ALLOCATOR_STAT_ALLOCATED = 0;
+    // resident bytes not backing live allocations
+    public static final int RUST_ALLOCATOR_STAT_FRAGMENTATION = 3;
+    public static final int RUST_ALLOCATOR_STAT_RESIDENT = 2;
+    // returned by a Rust native method returning long when it panicked, see checkRustResult()
+    public static final long RUST_PANIC = Long.MIN_VALUE;
     public static final int WINDOWS = 3;
     public static final int _32Bit = -2;
     public static final int arch;
@@ -54,6 +63,14 @@ public final class Os {
     private Os() {
     }
 
+    // Returns the result of a Rust native method returning long, or throws when the method panicked
+    public static long checkRustResult(long result) {
+        if (result == RUST_PANIC) {
+            throw CairoException.critical(0).put("Rust library panicked [report=").put(rustLastPanic()).put(']');
+        }
+        return result;
+    }
+
     public static native long compareAndSwap(long mem, long oldValue, long newValue);
 
     public static native long currentTimeMicros();
@@ -185,6 +202,16 @@ public final class Os {
 
     public static native long realloc(long mem, long size);
 
+    // Returns the report (thread, location, message and backtrace) of the last panic in the Rust library, or null.
+    // A Rust native method returning long returns RUST_PANIC when it panics. Methods returning void, a pointer or a
+    // boolean return nothing, 0 or false, so callers that must detect a panic have to poll this method instead. Panics
+    // are logged to the "rust" log once RustLog is initialised, and to stderr before that.
+    public static native String rustLastPanic();
+
+    // Returns the statistic in bytes, or -1 for an unknown stat or when the Rust library is built without the jemalloc
+    // feature.
+    public static native long rustAllocatorStat(int stat);
+
     public static int setCurrentThreadAffinity(int cpu) {
         if (cpu == -1) {
             return 0;
@@ -0,0 +1,73 @@
+/*******************************************************************************
+ *     ___                  _   ____  ____
+ *    / _ \ _   _  ___  ___| |_|  _ \| __ )
+ *   | | | | | | |/ _ \/ __| __| | | |  _ \
+ *   | |_| | |_| |  __/\__ \ |_| |_| | |_) |
+ *    \__\_\\__,_|\___||___/\__|____/|____/
+ *
+ *  Copyright (c) 2014-2019 Appsicle
+ *  Copyright (c) 2019-2024 QuestDB
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ ******************************************************************************/
+
+package io.questdb.test.log;
+
+import io.questdb.log.RustLog;
+import io.questdb.std.Os;
+import io.questdb.test.tools.LogCapture;
+import org.junit.After;
+import org.junit.Assert;
+import org.junit.Assume;
+import org.junit.Before;
+import org.junit.Test;
+
+public class RustLogTest {
+    private static final LogCapture capture = new LogCapture();
+
+    @Before
+    public void setUp() {
+        capture.start();
+    }
+
+    @After
+    public void tearDown() {
+        capture.stop();
+    }
+
+    @Test
+    public void testInitToleratesOutdatedLibrary() {
+        // The prebuilt library may not export the logger, init() must not fail startup either way.
+        boolean registered = RustLog.init(RustLog.LEVEL_INFO);
+        Assert.assertEquals(registered, RustLog.init(RustLog.LEVEL_DEBUG));
+        if (Os.type == Os._32Bit) {
+            Assert.assertFalse(registered);
+        }
+    }
+
+    @Test
+    public void testRecordsReachLog() {
+        Assume.assumeTrue("native logger is not available", RustLog.init(RustLog.LEVEL_INFO));
+        try {
+            RustLog.testLog(RustLog.LEVEL_DEBUG, "debug record of RustLogTest");
+            RustLog.testLog(RustLog.LEVEL_WARN, "warn record of RustLogTest");
+        } catch (UnsatisfiedLinkError e) {
+            Assume.assumeNoException("the Rust library is outdated", e);
+        }
+        capture.waitFor("warn record of RustLogTest");
+        capture.assertLoggedRE(" E rust \\[questdbr::logging\\] warn record of RustLogTest");
+        // records of a thread are written in order, the debug one would be there by now
+        capture.assertNotLogged("debug record of RustLogTest");
+    }
+}